@@ -0,0 +1,321 @@
+use std::borrow::Cow;
+
+use indexmap::map;
+
+use crate::keyfile::{Group, KeyValuePair, KeyValuePairMut};
+use crate::types::*;
+
+type EntryKey<'a> = (Cow<'a, str>, Option<Locale<'a>>);
+
+/// ## Entry for a [`KeyValuePair`] in a [`Group`], which is either occupied or vacant
+///
+/// This type is returned by [`Group::entry`], and works like the `Entry` types of other maps in the standard library:
+///
+/// ```
+/// use keyfile::{types::*, KeyFile};
+///
+/// let mut keyfile = KeyFile::parse("[Desktop Entry]\nName=Files\n").unwrap();
+/// let group = keyfile.get_group_mut("Desktop Entry").unwrap();
+///
+/// group
+///     .entry(Key::try_from("Name").unwrap(), None)
+///     .and_modify(|kv| {
+///         kv.set_string("Nautilus").unwrap();
+///     })
+///     .or_insert(Value::try_from("Files").unwrap());
+/// group.entry(Key::try_from("Icon").unwrap(), None).or_insert(Value::try_from("files").unwrap());
+///
+/// assert_eq!(group.to_string(), "[Desktop Entry]\nName=Nautilus\nIcon = files\n");
+/// ```
+#[derive(Debug)]
+pub enum Entry<'g, 'a> {
+    /// Entry for a key and locale that are already present in the [`Group`]
+    Occupied(OccupiedEntry<'g, 'a>),
+    /// Entry for a key and locale that are not present in the [`Group`]
+    Vacant(VacantEntry<'g, 'a>),
+}
+
+impl<'g, 'a> Entry<'g, 'a> {
+    pub(crate) fn new(entry: map::Entry<'g, EntryKey<'a>, KeyValuePair<'a>>, position: Option<usize>) -> Self {
+        match entry {
+            map::Entry::Occupied(inner) => Entry::Occupied(OccupiedEntry { inner }),
+            map::Entry::Vacant(inner) => Entry::Vacant(VacantEntry { inner, position }),
+        }
+    }
+
+    /// Method for getting the key string of the entry
+    pub fn key(&self) -> &str {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// Method for getting the optional locale of the entry
+    pub fn locale(&self) -> Option<&Locale<'a>> {
+        match self {
+            Entry::Occupied(entry) => entry.locale(),
+            Entry::Vacant(entry) => entry.locale(),
+        }
+    }
+
+    /// ### Method for inserting a new [`KeyValuePair`] with the given value if the entry is vacant
+    ///
+    /// Mutable access to the existing or inserted key-value pair is returned.
+    pub fn or_insert<'v: 'a>(self, default: Value<'v>) -> KeyValuePairMut<'g, 'a> {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /// ### Method for inserting a new [`KeyValuePair`] with the value returned by the function if the entry is vacant
+    ///
+    /// Mutable access to the existing or inserted key-value pair is returned.
+    pub fn or_insert_with<'v: 'a, F: FnOnce() -> Value<'v>>(self, default: F) -> KeyValuePairMut<'g, 'a> {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Method for modifying the existing [`KeyValuePair`] if the entry is occupied
+    pub fn and_modify<F: FnOnce(&mut KeyValuePairMut<'_, 'a>)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(&mut entry.get_mut());
+        }
+        self
+    }
+}
+
+/// ## Entry for a [`KeyValuePair`] that is present in a [`Group`]
+///
+/// This type is part of the [`Entry`] enum.
+#[derive(Debug)]
+pub struct OccupiedEntry<'g, 'a> {
+    inner: map::OccupiedEntry<'g, EntryKey<'a>, KeyValuePair<'a>>,
+}
+
+impl<'g, 'a> OccupiedEntry<'g, 'a> {
+    /// Method for getting the key string of the entry
+    pub fn key(&self) -> &str {
+        &self.inner.key().0
+    }
+
+    /// Method for getting the optional locale of the entry
+    pub fn locale(&self) -> Option<&Locale<'a>> {
+        self.inner.key().1.as_ref()
+    }
+
+    /// Method for getting a reference to the [`KeyValuePair`]
+    pub fn get(&self) -> &KeyValuePair<'a> {
+        self.inner.get()
+    }
+
+    /// Method for getting mutable access to the [`KeyValuePair`]
+    pub fn get_mut(&mut self) -> KeyValuePairMut<'_, 'a> {
+        KeyValuePairMut::new(self.inner.get_mut())
+    }
+
+    /// Method for converting the entry into mutable access to the [`KeyValuePair`] with the lifetime of the [`Group`]
+    pub fn into_mut(self) -> KeyValuePairMut<'g, 'a> {
+        KeyValuePairMut::new(self.inner.into_mut())
+    }
+
+    /// ### Method for setting the value of the [`KeyValuePair`]
+    ///
+    /// The replaced value string is returned.
+    pub fn insert<'v: 'a>(&mut self, value: Value<'v>) -> Cow<'a, str> {
+        self.inner.get_mut().set_value(value)
+    }
+
+    /// ### Method for removing the [`KeyValuePair`] from the [`Group`]
+    ///
+    /// This operation preserves the order of the remaining key-value pairs.
+    pub fn remove(self) -> KeyValuePair<'a> {
+        self.inner.shift_remove()
+    }
+}
+
+/// ## Entry for a [`KeyValuePair`] that is not present in a [`Group`]
+///
+/// This type is part of the [`Entry`] enum.
+#[derive(Debug)]
+pub struct VacantEntry<'g, 'a> {
+    inner: map::VacantEntry<'g, EntryKey<'a>, KeyValuePair<'a>>,
+    // position of a new translation next to the other key-value pairs with the same key
+    position: Option<usize>,
+}
+
+impl<'g, 'a> VacantEntry<'g, 'a> {
+    /// Method for getting the key string of the entry
+    pub fn key(&self) -> &str {
+        &self.inner.key().0
+    }
+
+    /// Method for getting the optional locale of the entry
+    pub fn locale(&self) -> Option<&Locale<'a>> {
+        self.inner.key().1.as_ref()
+    }
+
+    /// ### Method for inserting a new [`KeyValuePair`] with the given value
+    ///
+    /// The key-value pair is appended as the last entry in the [`Group`], except for translations of keys that are
+    /// already present, which are inserted after the last key-value pair with the same key (like with
+    /// [`Group::set_locale_string`]). Mutable access to the inserted key-value pair is returned.
+    pub fn insert<'v: 'a>(self, value: Value<'v>) -> KeyValuePairMut<'g, 'a> {
+        let (key, locale) = self.inner.key().clone();
+
+        let mut kv = KeyValuePair::new(Key::new_unchecked(key), value);
+        kv.locale = locale;
+
+        match self.position {
+            Some(index) => KeyValuePairMut::new(self.inner.shift_insert(index, kv)),
+            None => KeyValuePairMut::new(self.inner.insert(kv)),
+        }
+    }
+}
+
+/// ## Entry for a [`Group`] in a [`KeyFile`](crate::KeyFile), which is either occupied or vacant
+///
+/// This type is returned by [`KeyFile::entry`](crate::KeyFile::entry), and works like the `Entry` types of other maps
+/// in the standard library:
+///
+/// ```
+/// use keyfile::{types::*, KeyFile};
+///
+/// let mut keyfile = KeyFile::new();
+///
+/// for (name, value) in [("Name", "Files"), ("Icon", "files")] {
+///     keyfile
+///         .entry(GroupName::try_from("Desktop Entry").unwrap())
+///         .or_insert()
+///         .set_string(Key::try_from(name).unwrap(), value)
+///         .unwrap();
+/// }
+///
+/// assert_eq!(keyfile.to_string(), "[Desktop Entry]\nName = Files\nIcon = files\n");
+/// ```
+#[derive(Debug)]
+pub enum GroupEntry<'k, 'a> {
+    /// Entry for a group name that is already present in the [`KeyFile`](crate::KeyFile)
+    Occupied(OccupiedGroupEntry<'k, 'a>),
+    /// Entry for a group name that is not present in the [`KeyFile`](crate::KeyFile)
+    Vacant(VacantGroupEntry<'k, 'a>),
+}
+
+impl<'k, 'a> GroupEntry<'k, 'a> {
+    pub(crate) fn new(entry: map::Entry<'k, Cow<'a, str>, Group<'a>>, list_separator: ListSeparator) -> Self {
+        match entry {
+            map::Entry::Occupied(inner) => GroupEntry::Occupied(OccupiedGroupEntry { inner }),
+            map::Entry::Vacant(inner) => GroupEntry::Vacant(VacantGroupEntry { inner, list_separator }),
+        }
+    }
+
+    /// Method for getting the group name of the entry
+    pub fn name(&self) -> &str {
+        match self {
+            GroupEntry::Occupied(entry) => entry.name(),
+            GroupEntry::Vacant(entry) => entry.name(),
+        }
+    }
+
+    /// ### Method for inserting a new and empty [`Group`] if the entry is vacant
+    ///
+    /// A mutable reference to the existing or inserted group is returned.
+    pub fn or_insert(self) -> &'k mut Group<'a> {
+        match self {
+            GroupEntry::Occupied(entry) => entry.into_mut(),
+            GroupEntry::Vacant(entry) => {
+                let group = Group::new(GroupName::new_unchecked(entry.inner.key().clone()));
+                entry.insert(group)
+            },
+        }
+    }
+
+    /// ### Method for inserting the [`Group`] returned by the function if the entry is vacant
+    ///
+    /// A mutable reference to the existing or inserted group is returned. See [`VacantGroupEntry::insert`] for how the
+    /// inserted group is adapted to the [`KeyFile`](crate::KeyFile).
+    pub fn or_insert_with<'g: 'a, F: FnOnce() -> Group<'g>>(self, default: F) -> &'k mut Group<'a> {
+        match self {
+            GroupEntry::Occupied(entry) => entry.into_mut(),
+            GroupEntry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Method for modifying the existing [`Group`] if the entry is occupied
+    pub fn and_modify<F: FnOnce(&mut Group<'a>)>(mut self, f: F) -> Self {
+        if let GroupEntry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+/// ## Entry for a [`Group`] that is present in a [`KeyFile`](crate::KeyFile)
+///
+/// This type is part of the [`GroupEntry`] enum.
+#[derive(Debug)]
+pub struct OccupiedGroupEntry<'k, 'a> {
+    inner: map::OccupiedEntry<'k, Cow<'a, str>, Group<'a>>,
+}
+
+impl<'k, 'a> OccupiedGroupEntry<'k, 'a> {
+    /// Method for getting the group name of the entry
+    pub fn name(&self) -> &str {
+        self.inner.key()
+    }
+
+    /// Method for getting a reference to the [`Group`]
+    pub fn get(&self) -> &Group<'a> {
+        self.inner.get()
+    }
+
+    /// Method for getting a mutable reference to the [`Group`]
+    pub fn get_mut(&mut self) -> &mut Group<'a> {
+        self.inner.get_mut()
+    }
+
+    /// Method for converting the entry into a mutable reference to the [`Group`] with the lifetime of the
+    /// [`KeyFile`](crate::KeyFile)
+    pub fn into_mut(self) -> &'k mut Group<'a> {
+        self.inner.into_mut()
+    }
+
+    /// ### Method for removing the [`Group`] from the [`KeyFile`](crate::KeyFile)
+    ///
+    /// This operation preserves the order of the remaining groups.
+    pub fn remove(self) -> Group<'a> {
+        self.inner.shift_remove()
+    }
+}
+
+/// ## Entry for a [`Group`] that is not present in a [`KeyFile`](crate::KeyFile)
+///
+/// This type is part of the [`GroupEntry`] enum.
+#[derive(Debug)]
+pub struct VacantGroupEntry<'k, 'a> {
+    inner: map::VacantEntry<'k, Cow<'a, str>, Group<'a>>,
+    list_separator: ListSeparator,
+}
+
+impl<'k, 'a> VacantGroupEntry<'k, 'a> {
+    /// Method for getting the group name of the entry
+    pub fn name(&self) -> &str {
+        self.inner.key()
+    }
+
+    /// ### Method for inserting a [`Group`]
+    ///
+    /// The group is appended as the last group in the [`KeyFile`](crate::KeyFile), and a mutable reference to it is
+    /// returned. The name of the group is replaced with the name of the entry, and its list separator is replaced with
+    /// the list separator of the [`KeyFile`](crate::KeyFile).
+    pub fn insert<'g: 'a>(self, group: Group<'g>) -> &'k mut Group<'a> {
+        let mut group: Group<'a> = group;
+        group.name = self.inner.key().clone();
+        group.list_separator = self.list_separator;
+
+        self.inner.insert(group)
+    }
+}
@@ -0,0 +1,177 @@
+use std::borrow::Cow;
+
+use crate::keyfile::ValueError;
+
+/// Decode the escape sequences that are defined by the Desktop Entry Specification.
+///
+/// The supported escape sequences are `\s` (space), `\n` (newline), `\t` (tab), `\r` (carriage return), and `\\`
+/// (backslash). Any other escape sequence is invalid, as is an escape character at the end of the string. If the
+/// string does not contain any escape sequences, it is returned without copying.
+pub(crate) fn unescape(value: &str) -> Result<Cow<'_, str>, ValueError> {
+    unescape_with(value, None)
+}
+
+fn unescape_with(value: &str, separator: Option<char>) -> Result<Cow<'_, str>, ValueError> {
+    if !value.contains('\\') {
+        return Ok(Cow::Borrowed(value));
+    }
+
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('s') => unescaped.push(' '),
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('r') => unescaped.push('\r'),
+            Some('\\') => unescaped.push('\\'),
+            Some(other) if Some(other) == separator => unescaped.push(other),
+            Some(other) => return Err(ValueError::invalid_escape(other)),
+            None => return Err(ValueError::TrailingEscape),
+        }
+    }
+
+    Ok(Cow::Owned(unescaped))
+}
+
+/// Split a list of strings at every separator character that is not escaped and decode the list elements.
+///
+/// A separator at the end of the string does not start a new (empty) element, so both `a;b` and `a;b;` are parsed as
+/// a list of two elements. Elements that do not contain any escape sequences are returned without copying.
+pub(crate) fn split_list(value: &str, separator: char) -> Result<Vec<Cow<'_, str>>, ValueError> {
+    let mut elements = Vec::new();
+    let mut start = 0;
+    let mut chars = value.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        if c == '\\' {
+            // skip the escaped character, it is validated when the element is decoded
+            chars.next();
+        } else if c == separator {
+            elements.push(unescape_with(&value[start..i], Some(separator))?);
+            start = i + c.len_utf8();
+        }
+    }
+
+    if start < value.len() {
+        elements.push(unescape_with(&value[start..], Some(separator))?);
+    }
+
+    Ok(elements)
+}
+
+/// Encode a string with the escape sequences that are defined by the Desktop Entry Specification.
+///
+/// Newlines, tabs, carriage returns, and backslashes are always escaped, and leading spaces are escaped as `\s` so
+/// they are not lost when the value is parsed again. If the string does not contain any characters that need to be
+/// escaped, it is returned without copying.
+pub(crate) fn escape(value: &str) -> Cow<'_, str> {
+    escape_with(value, None)
+}
+
+fn escape_with(value: &str, separator: Option<char>) -> Cow<'_, str> {
+    let needs_escape = |c: char| matches!(c, '\n' | '\t' | '\r' | '\\') || Some(c) == separator;
+
+    if !value.starts_with(' ') && !value.contains(needs_escape) {
+        return Cow::Borrowed(value);
+    }
+
+    let mut escaped = String::with_capacity(value.len() + 8);
+    let mut leading = true;
+
+    for c in value.chars() {
+        match c {
+            ' ' if leading => escaped.push_str("\\s"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            '\\' => escaped.push_str("\\\\"),
+            c if Some(c) == separator => {
+                escaped.push('\\');
+                escaped.push(c);
+            },
+            c => escaped.push(c),
+        }
+
+        if c != ' ' {
+            leading = false;
+        }
+    }
+
+    Cow::Owned(escaped)
+}
+
+/// Join a list of strings into a single string, escaping separator characters that occur in list elements.
+///
+/// Every element is followed by a separator (including the last one), which matches the output of GLib.
+pub(crate) fn join_list<S: AsRef<str>>(values: &[S], separator: char) -> String {
+    let mut joined = String::new();
+
+    for value in values {
+        joined.push_str(&escape_with(value.as_ref(), Some(separator)));
+        joined.push(separator);
+    }
+
+    joined
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn test_unescape() {
+        assert!(matches!(unescape("plain value").unwrap(), Cow::Borrowed("plain value")));
+        assert_eq!(unescape(r"\sleading").unwrap(), " leading");
+        assert_eq!(unescape(r"one\ntwo\tthree\rfour").unwrap(), "one\ntwo\tthree\rfour");
+        assert_eq!(unescape(r"C:\\Windows").unwrap(), r"C:\Windows");
+
+        assert!(matches!(unescape(r"a\;b"), Err(ValueError::InvalidEscape { .. })));
+        assert!(matches!(unescape(r"trailing\"), Err(ValueError::TrailingEscape)));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert!(matches!(escape("plain value"), Cow::Borrowed("plain value")));
+        assert_eq!(escape("  leading and trailing  "), r"\s\sleading and trailing  ");
+        assert_eq!(escape("one\ntwo\tthree\rfour"), r"one\ntwo\tthree\rfour");
+        assert_eq!(escape(r"C:\Windows"), r"C:\\Windows");
+
+        for s in ["  x", "a\\b\nc", "\t tab", ""] {
+            assert_eq!(unescape(&escape(s)).unwrap(), s);
+        }
+    }
+
+    #[test]
+    fn test_split_list() {
+        assert!(split_list("", ';').unwrap().is_empty());
+        assert_eq!(split_list("GNOME;GTK;", ';').unwrap(), ["GNOME", "GTK"]);
+        assert_eq!(split_list("GNOME;GTK", ';').unwrap(), ["GNOME", "GTK"]);
+        assert_eq!(split_list("a;;b;", ';').unwrap(), ["a", "", "b"]);
+        assert_eq!(split_list(r"a\;b;c\\;d", ';').unwrap(), ["a;b", r"c\", "d"]);
+        assert_eq!(split_list(r"\sa;b\n", ';').unwrap(), [" a", "b\n"]);
+
+        assert!(matches!(
+            split_list(r"a\,b", ';'),
+            Err(ValueError::InvalidEscape { .. })
+        ));
+    }
+
+    #[test]
+    fn test_join_list() {
+        assert_eq!(join_list::<&str>(&[], ';'), "");
+        assert_eq!(join_list(&["GNOME", "GTK"], ';'), "GNOME;GTK;");
+        assert_eq!(join_list(&["a;b", r"c\", " d"], ';'), r"a\;b;c\\;\sd;");
+
+        let values = ["a;b", "", "c\nd", r"\"];
+        assert_eq!(split_list(&join_list(&values, ';'), ';').unwrap(), values);
+    }
+}
@@ -12,14 +12,21 @@
 //! string should yield a string that is an exact match for the original.
 
 use std::borrow::Cow;
+use std::collections::HashSet;
 use std::fmt::{self, Debug, Display};
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, Range};
 use std::str::FromStr;
 
-use indexmap::IndexMap;
+use indexmap::{Equivalent, IndexMap};
 use thiserror::Error;
 
-use crate::parse::{parse_as_header, parse_as_key_value_pair};
+use crate::entry::{Entry, GroupEntry};
+use crate::escape::{escape, join_list, split_list, unescape};
+use crate::parse::{parse_as_boolean, parse_as_double, parse_as_int64, parse_as_integer, parse_as_uint64, Parser};
+use crate::span::{render_snippet, Source, Span};
 use crate::types::*;
+use crate::write::LineWriter;
 
 #[cfg(doc)]
 use crate::types;
@@ -29,38 +36,528 @@ use crate::types;
 /// This error can be caused by various issues in the input string:
 ///
 /// - syntax errors (i.e. lines that are neither a valid group header, a valid key-value pair, a comment, or empty)
+/// - key-value pairs that occur before the first group header (and hence do not belong to any group)
 /// - invalid content (more than one group with the same name, or more than one key-value pair with the same key in the
 ///   same group)
 /// - violations of other invariants (for example, if a key with a locale specifier is present within a group, then the
 ///   same key *without* a locale specifier must also be present)
+///
+/// Every error contains the line number and the [`Span`] of the part of the input string that caused the error, and
+/// can be rendered together with the affected line of the input string with [`KeyFileError::render`].
 #[derive(Debug, Error)]
 pub enum KeyFileError {
     /// Error variant for syntax errors.
-    #[error("Invalid line (line {}): {}", .lineno, .line)]
+    #[error("Invalid line (line {}, column {}): {}: {}", .lineno, .span.start.column, .reason, .line)]
     #[allow(missing_docs)]
-    InvalidLine { line: String, lineno: usize },
+    InvalidLine {
+        line: String,
+        lineno: usize,
+        reason: InvalidLineReason,
+        span: Span,
+    },
     /// Error variant for multiple groups with the same name.
     #[error("Multiple groups with the same name (line {}): {}", .lineno, .name)]
     #[allow(missing_docs)]
-    DuplicateGroup { name: String, lineno: usize },
+    DuplicateGroup { name: String, lineno: usize, span: Span },
     /// Error variant for multiple keys in the same group with the same name.
     #[error("Multiple key-value pairs with the same key (line {}): {}", .lineno, .key)]
     #[allow(missing_docs)]
-    DuplicateKey { key: String, lineno: usize },
-    // error variant for missing locale-less key
+    DuplicateKey { key: String, lineno: usize, span: Span },
+    /// Error variant for key-value pairs that occur before the first group header.
+    #[error("Key-value pair outside of a group (line {}): {}", .lineno, .line)]
+    #[allow(missing_docs)]
+    UngroupedKeyValuePair { line: String, lineno: usize, span: Span },
+    /// Error variant for key-value pairs with a locale specifier, but without a matching key-value pair without one.
+    #[error("Translated key-value pair without an untranslated key-value pair (line {}): {}", .lineno, .key)]
+    #[allow(missing_docs)]
+    MissingBaseKey { key: String, lineno: usize, span: Span },
 }
 
 impl KeyFileError {
-    pub(crate) fn invalid_line(line: String, lineno: usize) -> Self {
-        KeyFileError::InvalidLine { line, lineno }
+    pub(crate) fn invalid_line(line: String, reason: InvalidLineReason, span: Span) -> Self {
+        KeyFileError::InvalidLine {
+            line,
+            lineno: span.start.line,
+            reason,
+            span,
+        }
+    }
+
+    pub(crate) fn duplicate_group(name: String, span: Span) -> Self {
+        KeyFileError::DuplicateGroup {
+            name,
+            lineno: span.start.line,
+            span,
+        }
+    }
+
+    pub(crate) fn duplicate_key(key: String, span: Span) -> Self {
+        KeyFileError::DuplicateKey {
+            key,
+            lineno: span.start.line,
+            span,
+        }
+    }
+
+    pub(crate) fn ungrouped_key_value_pair(line: String, span: Span) -> Self {
+        KeyFileError::UngroupedKeyValuePair {
+            line,
+            lineno: span.start.line,
+            span,
+        }
+    }
+
+    pub(crate) fn missing_base_key(key: String, span: Span) -> Self {
+        KeyFileError::MissingBaseKey {
+            key,
+            lineno: span.start.line,
+            span,
+        }
+    }
+
+    /// Method for getting the (zero-based) number of the line that caused the error
+    pub fn lineno(&self) -> usize {
+        self.span().start.line
+    }
+
+    /// Method for getting the (zero-based) byte offset of the error from the start of the line
+    pub fn column(&self) -> usize {
+        self.span().start.column
+    }
+
+    /// Method for getting the span of the part of the input string that caused the error
+    pub fn span(&self) -> Span {
+        match self {
+            KeyFileError::InvalidLine { span, .. }
+            | KeyFileError::DuplicateGroup { span, .. }
+            | KeyFileError::DuplicateKey { span, .. }
+            | KeyFileError::UngroupedKeyValuePair { span, .. }
+            | KeyFileError::MissingBaseKey { span, .. } => *span,
+        }
+    }
+
+    /// ### Method for rendering the error message together with the line of the input string that caused the error
+    ///
+    /// The line is printed below the error message, and the part of the line that caused the error is marked with `^`
+    /// characters. The input string needs to be the same string that was parsed.
+    ///
+    /// ```
+    /// use keyfile::KeyFile;
+    ///
+    /// let input = "[Desktop Entry]\nName[pt-BR]=Arquivos\n";
+    /// let error = KeyFile::parse(input).unwrap_err();
+    ///
+    /// assert_eq!(error.column(), 7);
+    /// assert_eq!(
+    ///     error.render(input),
+    ///     format!("{error}\n  |\n1 | Name[pt-BR]=Arquivos\n  |        ^\n"),
+    /// );
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        let span = self.span();
+        let line = source.lines().nth(span.start.line).unwrap_or_default();
+
+        let end = if span.end.line == span.start.line {
+            span.end.column
+        } else {
+            line.len()
+        };
+
+        render_snippet(self, line, Some(span.start.line), span.start.column..end)
+    }
+}
+
+/// ### Reason why a line could not be parsed
+///
+/// This is reported as part of [`KeyFileError::InvalidLine`] errors. The span of the error points at the part of the
+/// line that is described by the reason.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InvalidLineReason {
+    /// The line starts with whitespace (see [`ParseOptions::glib_compat`]).
+    LeadingWhitespace,
+    /// The line starts with `[`, but does not contain a matching `]`.
+    UnterminatedHeader,
+    /// The group name is empty or contains a character that is not allowed in group names.
+    InvalidGroupName,
+    /// The group header is followed by other characters.
+    TrailingCharacters,
+    /// The key is empty or contains a character that is not allowed in keys.
+    InvalidKey,
+    /// The locale specifier is not terminated by `]`.
+    UnterminatedLocale,
+    /// The locale specifier does not match the `lang_COUNTRY.ENCODING@MODIFIER` format.
+    InvalidLocale,
+    /// The key is not followed by the `=` separator.
+    MissingSeparator,
+    /// The value contains a control character.
+    InvalidValue,
+    /// The line could not be recognized as a group header or key-value pair.
+    Unrecognized,
+}
+
+impl Display for InvalidLineReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            InvalidLineReason::LeadingWhitespace => "leading whitespace is not allowed",
+            InvalidLineReason::UnterminatedHeader => "group header is missing the closing ']'",
+            InvalidLineReason::InvalidGroupName => "invalid character in group name",
+            InvalidLineReason::TrailingCharacters => "unexpected characters after group header",
+            InvalidLineReason::InvalidKey => "invalid character in key",
+            InvalidLineReason::UnterminatedLocale => "locale specifier is missing the closing ']'",
+            InvalidLineReason::InvalidLocale => "invalid locale specifier",
+            InvalidLineReason::MissingSeparator => "expected '=' after key",
+            InvalidLineReason::InvalidValue => "control character in value",
+            InvalidLineReason::Unrecognized => "unrecognized line",
+        };
+        f.write_str(reason)
+    }
+}
+
+/// ### Error that is returned when attempting to interpret a value as a specific type
+///
+/// Values are stored in their raw (i.e. escaped) form. This error is returned when the raw value of a key-value pair
+/// cannot be decoded into the requested type.
+#[derive(Debug, Error)]
+pub enum ValueError {
+    /// Error variant for escape sequences that are not defined by the specification.
+    #[error("Invalid escape sequence: {}", .sequence)]
+    #[allow(missing_docs)]
+    InvalidEscape { sequence: String },
+    /// Error variant for values that end with an incomplete escape sequence.
+    #[error("Escape character at end of value")]
+    TrailingEscape,
+    /// Error variant for values that cannot be interpreted as a boolean.
+    #[error("Value cannot be interpreted as a boolean: {}", .value)]
+    #[allow(missing_docs)]
+    InvalidBoolean { value: String },
+    /// Error variant for values that cannot be interpreted as a number.
+    #[error("Value cannot be interpreted as a number: {}", .value)]
+    #[allow(missing_docs)]
+    InvalidNumber { value: String },
+    /// Error variant for numbers that are out of range for the requested type.
+    #[error("Number is out of range: {}", .value)]
+    #[allow(missing_docs)]
+    OutOfRange { value: String },
+    /// Error variant for list elements that cannot be interpreted as the requested type.
+    #[error("Invalid list element (index {}): {}", .index, .source)]
+    #[allow(missing_docs)]
+    InvalidListElement { index: usize, source: Box<ValueError> },
+}
+
+impl ValueError {
+    pub(crate) fn invalid_escape(c: char) -> Self {
+        ValueError::InvalidEscape {
+            sequence: format!("\\{}", c),
+        }
+    }
+
+    pub(crate) fn invalid_boolean(value: &str) -> Self {
+        ValueError::InvalidBoolean {
+            value: String::from(value),
+        }
+    }
+
+    pub(crate) fn invalid_number(value: &str) -> Self {
+        ValueError::InvalidNumber {
+            value: String::from(value),
+        }
+    }
+
+    pub(crate) fn out_of_range(value: &str) -> Self {
+        ValueError::OutOfRange {
+            value: String::from(value),
+        }
+    }
+
+    pub(crate) fn invalid_list_element(index: usize, source: ValueError) -> Self {
+        ValueError::InvalidListElement {
+            index,
+            source: Box::new(source),
+        }
+    }
+}
+
+/// ### Error that is returned when the value of a key-value pair in a [`Group`] cannot be interpreted
+///
+/// This error wraps a [`ValueError`] and adds the name of the group and the key (including the locale specifier, if
+/// any) of the key-value pair whose value could not be interpreted.
+#[derive(Debug, Error)]
+#[error("Invalid value for key {} in group {}: {}", .key, .group, .source)]
+pub struct InvalidValue {
+    /// Name of the group that contains the key-value pair.
+    pub group: String,
+    /// Key of the key-value pair, including the locale specifier (if any).
+    pub key: String,
+    /// Reason why the value could not be interpreted.
+    pub source: ValueError,
+}
+
+impl InvalidValue {
+    pub(crate) fn new(group: &str, kv: &KeyValuePair, source: ValueError) -> Self {
+        InvalidValue {
+            group: String::from(group),
+            key: kv.compound_key(),
+            source,
+        }
+    }
+}
+
+/// ### Error that is returned when the key or locale of a key-value pair in a [`Group`] cannot be changed
+#[derive(Debug, Error)]
+pub enum RekeyError {
+    /// Error variant for keys that are not present in the group.
+    #[error("No key-value pair with this key: {}", .key)]
+    #[allow(missing_docs)]
+    NotFound { key: String },
+    /// Error variant for changes that would result in multiple key-value pairs with the same key in the group.
+    #[error("Key-value pair with this key already exists: {}", .key)]
+    #[allow(missing_docs)]
+    Collision { key: String },
+}
+
+/// ### Error that is returned when the target of [`KeyFile::get_comment`] or [`KeyFile::set_comment`] does not exist
+///
+/// [`KeyFile::set_comment`] also returns this error for invalid comment texts.
+#[derive(Debug, Error)]
+pub enum CommentError {
+    /// Error variant for groups that are not present in the keyfile.
+    #[error("No group with this name: {}", .name)]
+    #[allow(missing_docs)]
+    GroupNotFound { name: String },
+    /// Error variant for keys that are not present in the group.
+    #[error("No key-value pair with this key: {}", .key)]
+    #[allow(missing_docs)]
+    KeyNotFound { key: String },
+    /// Error variant for comment texts that contain control characters (other than tab and line breaks). The span
+    /// refers to the first invalid character in the comment text.
+    #[error("Invalid comment (column {}): may not contain control characters other than tab and line breaks", .span.start)]
+    #[allow(missing_docs)]
+    InvalidComment { span: Range<usize> },
+}
+
+/// ### Error that is returned when a [`Group`] in a [`KeyFile`] cannot be renamed or duplicated
+#[derive(Debug, Error)]
+pub enum RenameGroupError {
+    /// Error variant for groups that are not present in the keyfile.
+    #[error("No group with this name: {}", .name)]
+    #[allow(missing_docs)]
+    NotFound { name: String },
+    /// Error variant for changes that would result in multiple groups with the same name in the keyfile.
+    #[error("Group with this name already exists: {}", .name)]
+    #[allow(missing_docs)]
+    Collision { name: String },
+}
+
+/// ### Error that is returned when a group or key-value pair cannot be inserted at or moved to the requested position
+#[derive(Debug, Error)]
+pub enum PositionError {
+    /// Error variant for groups that are not present in the keyfile.
+    #[error("No group with this name: {}", .name)]
+    #[allow(missing_docs)]
+    GroupNotFound { name: String },
+    /// Error variant for keys that are not present in the group.
+    #[error("No key-value pair with this key: {}", .key)]
+    #[allow(missing_docs)]
+    KeyNotFound { key: String },
+    /// Error variant for positions that are past the last group or key-value pair.
+    #[error("Position {} is out of bounds (length is {})", .index, .len)]
+    #[allow(missing_docs)]
+    OutOfBounds { index: usize, len: usize },
+}
+
+/// ### Options for parsing a string into a [`KeyFile`]
+///
+/// The default options match the behaviour of [`KeyFile::parse`]. Options can be changed with the "builder" methods:
+///
+/// ```
+/// use keyfile::{types::ListSeparator, KeyFile, ParseOptions};
+///
+/// let options = ParseOptions::new().list_separator(ListSeparator::try_from(',').unwrap());
+/// let keyfile = KeyFile::parse_with_options("[Legacy]\nPaths=/usr,/opt,\n", &options).unwrap();
+///
+/// let paths = keyfile.get_group("Legacy").unwrap().get_string_list("Paths").unwrap();
+/// assert_eq!(paths.unwrap(), ["/usr", "/opt"]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ParseOptions {
+    pub(crate) list_separator: ListSeparator,
+    pub(crate) check_base_keys: bool,
+    pub(crate) glib_compat: bool,
+    pub(crate) duplicate_policy: DuplicatePolicy,
+    pub(crate) trailing_decor: TrailingDecor,
+}
+
+impl ParseOptions {
+    /// Method for creating a new set of [`ParseOptions`] with default values
+    pub fn new() -> Self {
+        ParseOptions::default()
+    }
+
+    /// ### Method for setting the separator character for list values
+    ///
+    /// The list separator of the parsed [`KeyFile`] is set to this value (see [`KeyFile::set_list_separator`]).
+    pub fn list_separator(mut self, separator: ListSeparator) -> Self {
+        self.list_separator = separator;
+        self
+    }
+
+    /// ### Method for enabling or disabling checks for translated keys without an untranslated key
+    ///
+    /// The specification requires that for every key-value pair with a locale specifier (`Key[locale]`), the same
+    /// group also contains a key-value pair for the same key without a locale specifier (`Key`). Some files that are
+    /// found in the wild violate this rule, so it is not checked by default. If this check is enabled, the first
+    /// violation in a group results in a [`KeyFileError::MissingBaseKey`] error.
+    ///
+    /// ```
+    /// use keyfile::{KeyFile, KeyFileError, ParseOptions};
+    ///
+    /// let input = "[Desktop Entry]\nName[de]=Dateien\n";
+    /// assert!(KeyFile::parse(input).is_ok());
+    ///
+    /// let options = ParseOptions::new().check_base_keys(true);
+    /// let error = KeyFile::parse_with_options(input, &options).unwrap_err();
+    /// assert!(matches!(error, KeyFileError::MissingBaseKey { lineno: 1, .. }));
+    /// ```
+    pub fn check_base_keys(mut self, enabled: bool) -> Self {
+        self.check_base_keys = enabled;
+        self
+    }
+
+    /// ### Method for enabling or disabling GLib-compatible handling of leading whitespace
+    ///
+    /// The specification does not allow whitespace at the start of a line, but GLib ignores it: Lines that only
+    /// contain whitespace are treated as empty lines, lines with a `#` character after leading whitespace are treated
+    /// as comments, and indented group headers and key-value pairs are accepted. GLib also accepts spaces and tabs after
+    /// the closing `]` of a group header. If this option is enabled, these lines are accepted as well. The whitespace
+    /// is preserved, so converting the [`KeyFile`] back into a string still yields the original string.
+    ///
+    /// ```
+    /// use keyfile::{KeyFile, ParseOptions};
+    ///
+    /// let input = "  # comment\n\t\n  [Desktop Entry]\n\tName=Files\n";
+    /// assert!(KeyFile::parse(input).is_err());
+    ///
+    /// let options = ParseOptions::new().glib_compat(true);
+    /// let keyfile = KeyFile::parse_with_options(input, &options).unwrap();
+    /// let group = keyfile.get_group("Desktop Entry").unwrap();
+    /// assert_eq!(group.get_string("Name").unwrap().unwrap(), "Files");
+    /// assert_eq!(keyfile.to_string(), input);
+    /// ```
+    pub fn glib_compat(mut self, enabled: bool) -> Self {
+        self.glib_compat = enabled;
+        self
     }
 
-    pub(crate) fn duplicate_group(name: String, lineno: usize) -> Self {
-        KeyFileError::DuplicateGroup { name, lineno }
+    /// ### Method for setting how multiple groups with the same name and multiple key-value pairs with the same key are
+    /// handled
+    ///
+    /// By default, duplicates result in [`KeyFileError::DuplicateGroup`] and [`KeyFileError::DuplicateKey`] errors.
+    /// For the other policies, duplicates are resolved and reported as warnings by [`KeyFile::parse_lenient`].
+    ///
+    /// ```
+    /// use keyfile::{DuplicatePolicy, KeyFile, ParseOptions};
+    ///
+    /// let input = "[Desktop Entry]\nName=Files\nName=Nautilus\n";
+    /// assert!(KeyFile::parse(input).is_err());
+    ///
+    /// let options = ParseOptions::new().duplicate_policy(DuplicatePolicy::LastWins);
+    /// let keyfile = KeyFile::parse_with_options(input, &options).unwrap();
+    /// let group = keyfile.get_group("Desktop Entry").unwrap();
+    /// assert_eq!(group.get_string("Name").unwrap().unwrap(), "Nautilus");
+    /// assert_eq!(keyfile.to_string(), input);
+    /// ```
+    pub fn duplicate_policy(mut self, policy: DuplicatePolicy) -> Self {
+        self.duplicate_policy = policy;
+        self
+    }
+
+    /// ### Method for setting which item comments and empty lines after the last key-value pair of a group belong to
+    ///
+    /// By default, they are associated with the next group (see [`TrailingDecor`] for details). When groups are removed
+    /// or reordered, comments that describe the end of the previous group can be kept with that group instead:
+    ///
+    /// ```
+    /// use keyfile::{KeyFile, ParseOptions, TrailingDecor};
+    ///
+    /// let input = "[A]\nName=a\n# end of A\n\n[B]\nName=b\n";
+    ///
+    /// let mut keyfile = KeyFile::parse(input).unwrap();
+    /// keyfile.remove_group("B");
+    /// assert_eq!(keyfile.to_string(), "[A]\nName=a\n");
+    ///
+    /// let options = ParseOptions::new().trailing_decor(TrailingDecor::PreviousGroup);
+    /// let mut keyfile = KeyFile::parse_with_options(input, &options).unwrap();
+    /// keyfile.remove_group("B");
+    /// assert_eq!(keyfile.to_string(), "[A]\nName=a\n# end of A\n\n");
+    /// ```
+    pub fn trailing_decor(mut self, attachment: TrailingDecor) -> Self {
+        self.trailing_decor = attachment;
+        self
     }
+}
 
-    pub(crate) fn duplicate_key(key: String, lineno: usize) -> Self {
-        KeyFileError::DuplicateKey { key, lineno }
+/// ### Policy for handling multiple groups with the same name and multiple key-value pairs with the same key
+///
+/// Except for [`DuplicatePolicy::Merge`], the lines of groups and key-value pairs that are shadowed by a duplicate are
+/// preserved verbatim at their original position, so converting the parsed [`KeyFile`] back into a string still yields
+/// the original string. These lines are part of the decor of the next item, but they are never treated as comment
+/// lines (see [`KeyFile::get_comment`]).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DuplicatePolicy {
+    /// Duplicates are treated as errors
+    #[default]
+    Error,
+    /// The first group or key-value pair is used, later duplicates are preserved verbatim (lines of later groups are
+    /// still checked for invalid lines and duplicate keys)
+    FirstWins,
+    /// The last group or key-value pair is used, earlier duplicates are preserved verbatim
+    LastWins,
+    /// Key-value pairs of groups with the same name are merged into the first group, and for key-value pairs with the
+    /// same key, the last one is used (which matches the behaviour of GLib)
+    ///
+    /// The header lines of later groups are removed and their key-value pairs are moved to the end of the first group,
+    /// so converting the parsed [`KeyFile`] back into a string does not yield the original string.
+    Merge,
+}
+
+/// ### Item that comments and empty lines after the last key-value pair of a group are associated with
+///
+/// Comments and empty lines between key-value pairs always belong to the following key-value pair, and comments and
+/// empty lines at the top of the file belong to the [`KeyFile`] or the first group (see [`KeyFile::get_comment`]).
+/// Lines that are moved between items after parsing are written at their new position.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TrailingDecor {
+    /// Lines are associated with the next group (see [`Group::get_decor`]), and lines after the last group are
+    /// associated with the [`KeyFile`] itself (see [`KeyFile::get_decor`])
+    #[default]
+    NextItem,
+    /// Lines are associated with the end of the previous group (see [`Group::get_trailing`]), including lines after the
+    /// last group
+    PreviousGroup,
+}
+
+/// ### Line ending style of a [`KeyFile`]
+///
+/// When parsing a string, the [`LineEnding::CrLf`] style is detected if every line ends with `\r\n`. Otherwise, the
+/// [`LineEnding::Lf`] style is used, and the carriage return of every line that ends with `\r\n` is preserved as part
+/// of that line, so that files with mixed line endings are written back unchanged (see [`KeyFile::set_line_ending`]
+/// for normalizing them).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum LineEnding {
+    /// UNIX-style line endings (`\n`)
+    #[default]
+    Lf,
+    /// Windows-style line endings (`\r\n`)
+    CrLf,
+}
+
+impl LineEnding {
+    /// Method for getting the string representation of the line ending
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
     }
 }
 
@@ -69,7 +566,9 @@ impl KeyFileError {
 /// A KeyFile contains multiple named groups of key-value pairs, i.e. provides a two-level mapping.
 ///
 /// Any trailing empty lines or comment lines ("decor") that occur after the last group / key-value pair are assumed to
-/// be associated with the top-level keyfile itself, and are preserved across edits.
+/// be associated with the top-level keyfile itself, and are preserved across edits. The same applies to the comment at
+/// the top of the file, i.e. the lines before the first group header up to the last empty line. Comment lines that
+/// directly precede the first group header belong to that group instead (see [`KeyFile::get_comment`]).
 ///
 /// Two methods are provided for parsing a string as a [`KeyFile`]:
 ///
@@ -81,20 +580,58 @@ impl KeyFileError {
 /// The second method is equivalent to calling [`KeyFile::parse`] first and then calling [`KeyFile::into_owned`] on the
 /// result, and is privoded for convenience.
 ///
+/// The zero-copy parser can be configured with [`ParseOptions`] (see [`KeyFile::parse_with_options`]). Files that are
+/// not strictly valid can be parsed with [`KeyFile::parse_lenient`] or [`KeyFile::parse_with_errors`], which preserve
+/// invalid lines verbatim and report problems instead of failing on the first one.
+///
 /// A [`KeyFile`] can also be constructed programmatically by initializing an empty keyfile with [`KeyFile::new`] and
 /// then inserting groups with [`KeyFile::insert_group`].
-#[derive(Clone, Debug, Default)]
+///
+/// Every [`KeyFile`] has a separator character for list values, which defaults to `;` and is used by all list getters
+/// and setters of the groups it contains (i.e. [`Group::get_string_list`] and similar methods).
+///
+/// The line ending style ([`LineEnding`]) and whether the last line is terminated by a line ending are detected when
+/// parsing a string, and are reproduced when writing the [`KeyFile`]. They can be normalized explicitly with
+/// [`KeyFile::set_line_ending`] and [`KeyFile::set_final_newline`]:
+///
+/// ```
+/// use keyfile::{KeyFile, LineEnding};
+///
+/// let mut keyfile = KeyFile::parse("[Hello World]\r\none=one").unwrap();
+/// assert_eq!(keyfile.to_string(), "[Hello World]\r\none=one");
+///
+/// keyfile.set_line_ending(LineEnding::Lf);
+/// keyfile.set_final_newline(true);
+/// assert_eq!(keyfile.to_string(), "[Hello World]\none=one\n");
+/// ```
+#[derive(Clone, Debug)]
 pub struct KeyFile<'a> {
+    pub(crate) header: Decor<'a>,
     pub(crate) groups: IndexMap<Cow<'a, str>, Group<'a>>,
-    pub(crate) decor: Vec<Cow<'a, str>>,
+    pub(crate) decor: Decor<'a>,
+    pub(crate) list_separator: ListSeparator,
+    pub(crate) line_ending: LineEnding,
+    pub(crate) final_newline: bool,
 }
 
 impl<'a> KeyFile<'a> {
     /// Method for creating a new and empty [`KeyFile`]
     pub fn new() -> Self {
+        KeyFile::new_with_list_separator(ListSeparator::default())
+    }
+
+    /// ### Method for creating a new and empty [`KeyFile`] with a custom list separator
+    ///
+    /// This method is equivalent to [`KeyFile::new`] except that it also allows setting the separator character for
+    /// list values.
+    pub fn new_with_list_separator(separator: ListSeparator) -> Self {
         KeyFile {
+            header: Decor::default(),
             groups: IndexMap::new(),
-            decor: Vec::new(),
+            decor: Decor::default(),
+            list_separator: separator,
+            line_ending: LineEnding::default(),
+            final_newline: true,
         }
     }
 
@@ -103,73 +640,96 @@ impl<'a> KeyFile<'a> {
     /// This method does not copy any part of the input string and returns a value whose lifetime is tied to the
     /// lifetime of the input string.
     pub fn parse(value: &'a str) -> Result<Self, KeyFileError> {
-        let mut current_group: Option<Group> = None;
-
-        let mut groups: IndexMap<Cow<str>, Group> = IndexMap::new();
-        let mut decor = Vec::new();
+        KeyFile::parse_with_options(value, &ParseOptions::default())
+    }
 
-        for (lineno, line) in value.lines().enumerate() {
-            // - empty lines are not meaningful
-            // - lines that begin with a "#" character are comments
-            if line.is_empty() || line.starts_with('#') {
-                decor.push(Cow::Borrowed(line));
+    /// ### Method for parsing a string into a [`KeyFile`] with non-default options
+    ///
+    /// This method is equivalent to [`KeyFile::parse`] except that it also allows setting [`ParseOptions`].
+    pub fn parse_with_options(value: &'a str, options: &ParseOptions) -> Result<Self, KeyFileError> {
+        let (keyfile, _warnings) = Parser::new(value, options, false).parse()?;
+        Ok(keyfile)
+    }
 
-            // attempt to parse line as group header
-            } else if let Some(header) = parse_as_header(line) {
-                if groups.contains_key(header) {
-                    return Err(KeyFileError::duplicate_group(String::from(header), lineno));
-                }
-                if let Some(collector) = current_group.take() {
-                    // this clone is cheap since collector.name is always a Cow::Borrowed
-                    // TODO: validate that when inserting the "finished" group, there is a locale-less key-value-pair
-                    // for every locale-ful key-value-pair
-                    groups.insert(collector.name.clone(), collector);
-                    // already checked if there was a previous group with this name
-                }
-                current_group = Some(Group::from_entries(
-                    GroupName::new_unchecked(header.into()),
-                    IndexMap::new(),
-                    Decor::new_unchecked(std::mem::take(&mut decor)),
-                ));
-
-            // attempt to parse line as key-value-pair
-            } else if let Some((key, locale, value, wsl, wsr)) = parse_as_key_value_pair(line) {
-                if let Some(collector) = &mut current_group {
-                    let key_str = if let Some(ref locale) = &locale {
-                        format!("{}[{}]", key, locale)
-                    } else {
-                        key.to_string()
-                    };
-
-                    let kv = KeyValuePair::from_fields(
-                        Key::new_unchecked(key.into()),
-                        // this clone is cheap since locale contains only Cow::Borrowed
-                        locale.clone(),
-                        Value::new_unchecked(value.into()),
-                        Whitespace::new_unchecked(wsl.into()),
-                        Whitespace::new_unchecked(wsr.into()),
-                        Decor::new_unchecked(std::mem::take(&mut decor)),
-                    );
-                    if let Some(_previous) = collector.entries.insert((key.into(), locale), kv) {
-                        return Err(KeyFileError::duplicate_key(key_str, lineno));
-                    }
-                }
+    /// ### Method for parsing a string into a [`KeyFile`] without failing on invalid lines
+    ///
+    /// This method is equivalent to [`KeyFile::parse_with_options`] except that lines which cannot be parsed (i.e.
+    /// lines that would result in a [`KeyFileError::InvalidLine`] or [`KeyFileError::UngroupedKeyValuePair`] error)
+    /// are preserved verbatim instead, and the errors are returned as warnings. If checks for missing untranslated
+    /// keys are enabled (see [`ParseOptions::check_base_keys`]), violations are returned as warnings as well.
+    ///
+    /// Preserved lines are stored like comments: They are attached to the next key-value pair in the same group, or to
+    /// the end of the group if there is no following key-value pair. Lines before the first group header are split like
+    /// comments at the top of the file: Lines up to the last empty line belong to the [`KeyFile`] itself, and the
+    /// remaining lines are attached to the first group. Such lines are not returned as comments by
+    /// [`KeyFile::get_comment`]. Converting the [`KeyFile`] back into a string still yields the original string, which
+    /// makes it possible to modify files that are not strictly valid without losing any lines.
+    ///
+    /// Multiple groups with the same name or multiple key-value pairs with the same key are still treated as errors,
+    /// unless a different [`DuplicatePolicy`] is configured (see [`ParseOptions::duplicate_policy`]).
+    ///
+    /// ```
+    /// use keyfile::{KeyFile, KeyFileError, ParseOptions};
+    ///
+    /// let input = "[Desktop Entry]\nName=Firefox\nName[ja_JP-mac]=Firefox\n";
+    /// assert!(KeyFile::parse(input).is_err());
+    ///
+    /// let (keyfile, warnings) = KeyFile::parse_lenient(input, &ParseOptions::default()).unwrap();
+    /// assert!(matches!(warnings.as_slice(), [KeyFileError::InvalidLine { lineno: 2, .. }]));
+    /// assert_eq!(keyfile.to_string(), input);
+    /// ```
+    pub fn parse_lenient(value: &'a str, options: &ParseOptions) -> Result<(Self, Vec<KeyFileError>), KeyFileError> {
+        Parser::new(value, options, true).parse()
+    }
 
-            // line is invalid if it is neither empty, nor a comment, nor a group header, nor a key-value-pair
-            } else {
-                return Err(KeyFileError::invalid_line(String::from(line), lineno));
-            }
+    /// ### Method for parsing a string into a [`KeyFile`] while collecting all errors
+    ///
+    /// This method does not stop at the first error, but returns all errors that were found in the input string
+    /// (ordered by line number), together with a [`KeyFile`] that contains all valid parts of the input string.
+    ///
+    /// Invalid lines are handled like in [`KeyFile::parse_lenient`]. Multiple groups with the same name and multiple
+    /// key-value pairs with the same key are reported as errors as well. If the configured [`DuplicatePolicy`] is
+    /// [`DuplicatePolicy::Error`], duplicates are resolved with [`DuplicatePolicy::FirstWins`], otherwise they are
+    /// resolved according to the configured policy. The returned [`KeyFile`] can be converted back into the original
+    /// string in both cases (except for [`DuplicatePolicy::Merge`]).
+    ///
+    /// If the returned list of errors is empty, the result is the same as the result of
+    /// [`KeyFile::parse_with_options`].
+    ///
+    /// ```
+    /// use keyfile::{KeyFile, KeyFileError, ParseOptions};
+    ///
+    /// let input = "[Desktop Entry]\nName=Files\nName=Nautilus\nIcon\n[Desktop Entry]\n";
+    /// let (keyfile, errors) = KeyFile::parse_with_errors(input, &ParseOptions::default());
+    ///
+    /// assert!(matches!(
+    ///     errors.as_slice(),
+    ///     [
+    ///         KeyFileError::DuplicateKey { lineno: 2, .. },
+    ///         KeyFileError::InvalidLine { lineno: 3, .. },
+    ///         KeyFileError::DuplicateGroup { lineno: 4, .. },
+    ///     ]
+    /// ));
+    ///
+    /// let group = keyfile.get_group("Desktop Entry").unwrap();
+    /// assert_eq!(group.get_string("Name").unwrap().unwrap(), "Files");
+    /// assert_eq!(keyfile.to_string(), input);
+    /// ```
+    pub fn parse_with_errors(value: &'a str, options: &ParseOptions) -> (Self, Vec<KeyFileError>) {
+        let mut options = options.clone();
+        if options.duplicate_policy == DuplicatePolicy::Error {
+            options.duplicate_policy = DuplicatePolicy::FirstWins;
         }
 
-        if let Some(collector) = current_group.take() {
-            // this clone is cheap since collector.name is always a Cow::Borrowed
-            // TODO: validate that when inserting the "finished" group, there is a locale-less key-value-pair for every
-            // locale-ful key-value-pair
-            groups.insert(collector.name.clone(), collector);
-            // already checked if there was a previous group with this name
-        }
+        // lenient parsing only fails for duplicates, which are not errors with this policy
+        let (keyfile, mut errors) = Parser::new(value, &options, true)
+            .parse()
+            .unwrap_or_else(|error| (KeyFile::new(), vec![error]));
+
+        // checks for missing untranslated keys are only performed at the end of each group
+        errors.sort_by_key(KeyFileError::lineno);
 
-        Ok(KeyFile { groups, decor })
+        (keyfile, errors)
     }
 
     /// ### Method for converting a `KeyFile<'a>` into a `KeyFile<'static>`
@@ -177,76 +737,510 @@ impl<'a> KeyFile<'a> {
     /// This is a "deep copy" which converts any [`Cow::Borrowed`] into [`Cow::Owned`] by copying the underlying string
     /// into a new "owned" value.
     pub fn into_owned(self) -> KeyFile<'static> {
-        let mut owned = KeyFile::new();
+        let mut owned = KeyFile::new_with_list_separator(self.list_separator);
+        owned.line_ending = self.line_ending;
+        owned.final_newline = self.final_newline;
+
+        owned.header = self.header.into_owned();
 
         for (_group_name, group) in self.groups {
             owned.insert_group(group.into_owned());
         }
 
-        for line in self.decor {
-            owned.decor.push(Cow::Owned(line.into_owned()));
-        }
+        owned.decor = self.decor.into_owned();
 
         owned
     }
 
+    /// ### Method for iterating over the groups of the [`KeyFile`]
+    ///
+    /// Groups are returned in the order in which they appear in the file (like `g_key_file_get_groups`).
+    ///
+    /// ```
+    /// use keyfile::KeyFile;
+    ///
+    /// let keyfile = KeyFile::parse("[Desktop Entry]\nName=Files\n[Desktop Action new]\n").unwrap();
+    ///
+    /// let names: Vec<&str> = keyfile.group_names().collect();
+    /// assert_eq!(names, ["Desktop Entry", "Desktop Action new"]);
+    ///
+    /// let sizes: Vec<usize> = keyfile.groups().map(|group| group.entries().count()).collect();
+    /// assert_eq!(sizes, [1, 0]);
+    /// ```
+    pub fn groups(&self) -> impl Iterator<Item = &Group<'a>> {
+        self.groups.values()
+    }
+
+    /// ### Method for iterating over mutable references to the groups of the [`KeyFile`]
+    ///
+    /// See [`KeyFile::groups`].
+    pub fn groups_mut(&mut self) -> impl Iterator<Item = &mut Group<'a>> {
+        self.groups.values_mut()
+    }
+
+    /// ### Method for iterating over the names of the groups of the [`KeyFile`]
+    ///
+    /// See [`KeyFile::groups`].
+    pub fn group_names(&self) -> impl Iterator<Item = &str> {
+        self.groups.keys().map(AsRef::as_ref)
+    }
+
     /// ### Method for getting a reference to the [`Group`] with the given name
     ///
     /// If there is no group with the given name, then [`None`] is returned.
-    pub fn get_group(&self, name: &str) -> Option<&Group> {
+    pub fn get_group(&self, name: &str) -> Option<&Group<'a>> {
         self.groups.get(name)
     }
 
     /// ### Method for getting a mutable reference to the [`Group`] with the given name
     ///
     /// If there is no group with the given name, then [`None`] is returned.
-    pub fn get_group_mut(&'a mut self, name: &str) -> Option<&'a mut Group<'a>> {
+    pub fn get_group_mut(&mut self, name: &str) -> Option<&mut Group<'a>> {
         self.groups.get_mut(name)
     }
 
+    /// ### Method for getting the entry for the [`Group`] with the given name for in-place manipulation
+    ///
+    /// See [`GroupEntry`] for an example.
+    pub fn entry<'n: 'a>(&mut self, name: GroupName<'n>) -> GroupEntry<'_, 'a> {
+        GroupEntry::new(self.groups.entry(name.into()), self.list_separator)
+    }
+
     /// ### Method for inserting a new [`Group`] into the [`KeyFile`]
     ///
     /// The group will be appended as the last group in the [`KeyFile`].
     ///
     /// Inserting a group with the same name as an already existing group will
     /// replace the existing group. In this case, the replaced group is returned.
-    pub fn insert_group<'g: 'a>(&mut self, group: Group<'g>) -> Option<Group> {
+    ///
+    /// The list separator of the inserted group is set to the list separator of the [`KeyFile`].
+    pub fn insert_group<'g: 'a>(&mut self, mut group: Group<'g>) -> Option<Group<'a>> {
+        group.list_separator = self.list_separator;
+
         // This clone is cheap only if the group.name is a Cow::Borrowed(&str).
         // If group.name is a Cow::Owned(String), the String needs to be copied.
         self.groups.insert(group.name.clone(), group)
     }
 
-    /// ### Method for removing a [`Group`] with the given name
+    /// ### Method for inserting a new [`Group`] into the [`KeyFile`] before the group with the given name
     ///
-    /// If there is no group with the given name, then [`None`] is returned.
+    /// This method is equivalent to [`KeyFile::insert_group`] except for the position of the inserted group. If a group
+    /// with the same name already exists, it is replaced and moved to the new position. If there is no group with the
+    /// given name, then an error is returned and the [`KeyFile`] is not modified.
     ///
-    /// This operation preserves the order of remaining groups.
-    pub fn remove_group(&mut self, name: &str) -> Option<Group> {
-        self.groups.shift_remove(name)
+    /// ```
+    /// use keyfile::{types::GroupName, Group, KeyFile};
+    ///
+    /// let mut keyfile = KeyFile::parse("[Desktop Entry]\n[Desktop Action new-window]\n").unwrap();
+    ///
+    /// let group = Group::new(GroupName::try_from("Desktop Action open").unwrap());
+    /// keyfile.insert_group_before("Desktop Action new-window", group).unwrap();
+    /// assert_eq!(keyfile.to_string(), "[Desktop Entry]\n[Desktop Action open]\n[Desktop Action new-window]\n");
+    ///
+    /// keyfile.move_group("Desktop Action new-window", 1).unwrap();
+    /// assert_eq!(keyfile.to_string(), "[Desktop Entry]\n[Desktop Action new-window]\n[Desktop Action open]\n");
+    /// ```
+    pub fn insert_group_before<'g: 'a>(
+        &mut self,
+        name: &str,
+        group: Group<'g>,
+    ) -> Result<Option<Group<'a>>, PositionError> {
+        self.insert_group_next_to(name, group, false)
     }
-}
 
-impl<'a> Display for KeyFile<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for (_name, group) in &self.groups {
-            write!(f, "{}", group)?;
-        }
+    /// ### Method for inserting a new [`Group`] into the [`KeyFile`] after the group with the given name
+    ///
+    /// See [`KeyFile::insert_group_before`].
+    pub fn insert_group_after<'g: 'a>(
+        &mut self,
+        name: &str,
+        group: Group<'g>,
+    ) -> Result<Option<Group<'a>>, PositionError> {
+        self.insert_group_next_to(name, group, true)
+    }
 
-        for line in &self.decor {
-            writeln!(f, "{}", line)?;
+    fn insert_group_next_to<'g: 'a>(
+        &mut self,
+        name: &str,
+        mut group: Group<'g>,
+        after: bool,
+    ) -> Result<Option<Group<'a>>, PositionError> {
+        group.list_separator = self.list_separator;
+
+        insert_next_to(&mut self.groups, name, group.name.clone(), group, after).ok_or_else(|| {
+            PositionError::GroupNotFound {
+                name: String::from(name),
+            }
+        })
+    }
+
+    /// ### Method for moving the [`Group`] with the given name to the given position
+    ///
+    /// Groups between the old and the new position are shifted to make room. The comment at the top of the file (see
+    /// [`KeyFile::get_comment`]) is not part of any group and stays at the top.
+    ///
+    /// If there is no group with the given name, or if the position is not less than the number of groups, then an
+    /// error is returned and the [`KeyFile`] is not modified.
+    pub fn move_group(&mut self, name: &str, index: usize) -> Result<(), PositionError> {
+        let from = self
+            .groups
+            .get_index_of(name)
+            .ok_or_else(|| PositionError::GroupNotFound {
+                name: String::from(name),
+            })?;
+        move_to(&mut self.groups, from, index)
+    }
+
+    /// ### Method for changing the name of the [`Group`] with the given name
+    ///
+    /// The group keeps its position in the [`KeyFile`], its comments, and its key-value pairs.
+    ///
+    /// If there is no group with the given name, or if there already is a different group with the new name, then an
+    /// error is returned and the [`KeyFile`] is not modified.
+    ///
+    /// ```
+    /// use keyfile::{types::GroupName, KeyFile, RenameGroupError};
+    ///
+    /// let mut keyfile = KeyFile::parse("[Desktop Entry]\n# action\n[Desktop Action new]\n[End]\n").unwrap();
+    ///
+    /// keyfile.rename_group("Desktop Action new", GroupName::try_from("Desktop Action open").unwrap()).unwrap();
+    /// assert_eq!(keyfile.to_string(), "[Desktop Entry]\n# action\n[Desktop Action open]\n[End]\n");
+    ///
+    /// let error = keyfile.rename_group("End", GroupName::try_from("Desktop Entry").unwrap()).unwrap_err();
+    /// assert!(matches!(error, RenameGroupError::Collision { .. }));
+    /// ```
+    pub fn rename_group<'n: 'a>(&mut self, name: &str, new_name: GroupName<'n>) -> Result<(), RenameGroupError> {
+        let new_name: Cow<'a, str> = new_name.into();
+
+        let Some(index) = self.groups.get_index_of(name) else {
+            return Err(RenameGroupError::NotFound {
+                name: String::from(name),
+            });
+        };
+
+        match self.groups.get_index_of(new_name.as_ref()) {
+            Some(other) if other == index => return Ok(()),
+            Some(_) => {
+                return Err(RenameGroupError::Collision {
+                    name: new_name.into_owned(),
+                })
+            },
+            None => {},
         }
 
+        let Some((_, mut group)) = self.groups.shift_remove_index(index) else {
+            return Err(RenameGroupError::NotFound {
+                name: String::from(name),
+            });
+        };
+
+        group.name = new_name.clone();
+        self.groups.insert(new_name, group);
+        self.groups.move_index(self.groups.len() - 1, index);
+
         Ok(())
     }
-}
-
-impl<'a> FromStr for KeyFile<'a> {
-    type Err = KeyFileError;
 
-    /// ### Parse a string into a [`KeyFile`]
+    /// ### Method for inserting a copy of the [`Group`] with the given name under a new name
     ///
-    /// *Note*: This method performs a "deep copy" of all string references in order to return a [`KeyFile`] with a
-    /// `'static` lifetime. When this is not required, the [`KeyFile::parse`] method only performs zero-copy parsing,
+    /// The copy includes the comments and key-value pairs of the group, and is inserted right after the original group.
+    /// A mutable reference to the copy is returned.
+    ///
+    /// If there is no group with the given name, or if there already is a group with the new name, then an error is
+    /// returned and the [`KeyFile`] is not modified.
+    ///
+    /// ```
+    /// use keyfile::{types::*, KeyFile};
+    ///
+    /// let mut keyfile = KeyFile::parse("[Desktop Action new]\nName=New Window\nExec=app --new\n").unwrap();
+    ///
+    /// let copy = keyfile
+    ///     .duplicate_group("Desktop Action new", GroupName::try_from("Desktop Action private").unwrap())
+    ///     .unwrap();
+    /// copy.set_string(Key::try_from("Name").unwrap(), "New Private Window").unwrap();
+    ///
+    /// assert_eq!(
+    ///     keyfile.to_string(),
+    ///     "[Desktop Action new]\nName=New Window\nExec=app --new\n\
+    ///      [Desktop Action private]\nName=New Private Window\nExec=app --new\n"
+    /// );
+    /// ```
+    pub fn duplicate_group<'n: 'a>(
+        &mut self,
+        name: &str,
+        new_name: GroupName<'n>,
+    ) -> Result<&mut Group<'a>, RenameGroupError> {
+        let new_name: Cow<'a, str> = new_name.into();
+
+        let Some((index, _, group)) = self.groups.get_full(name) else {
+            return Err(RenameGroupError::NotFound {
+                name: String::from(name),
+            });
+        };
+
+        if self.groups.contains_key(new_name.as_ref()) {
+            return Err(RenameGroupError::Collision {
+                name: new_name.into_owned(),
+            });
+        }
+
+        let mut copy = group.clone();
+        copy.name = new_name.clone();
+
+        let (copy_index, _) = self.groups.insert_full(new_name, copy);
+        self.groups.move_index(copy_index, index + 1);
+
+        match self.groups.get_index_mut(index + 1) {
+            Some((_, copy)) => Ok(copy),
+            None => Err(RenameGroupError::NotFound {
+                name: String::from(name),
+            }),
+        }
+    }
+
+    /// ### Method for removing a [`Group`] with the given name
+    ///
+    /// If there is no group with the given name, then [`None`] is returned.
+    ///
+    /// This operation preserves the order of remaining groups.
+    pub fn remove_group(&mut self, name: &str) -> Option<Group<'a>> {
+        self.groups.shift_remove(name)
+    }
+
+    /// Method for getting the line ending style
+    pub fn get_line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
+    /// ### Method for setting the line ending style
+    ///
+    /// All lines are written with the new line ending style, including lines of files with mixed line endings whose
+    /// line endings were preserved individually (see [`LineEnding`]). The replaced line ending style is returned.
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) -> LineEnding {
+        strip_carriage_returns(self.header.lines_mut());
+        strip_carriage_returns(self.decor.lines_mut());
+
+        for group in self.groups.values_mut() {
+            group.cr = false;
+            strip_carriage_returns(group.decor.lines_mut());
+            strip_carriage_returns(group.trailing.lines_mut());
+
+            for kv in group.entries.values_mut() {
+                kv.cr = false;
+                strip_carriage_returns(kv.decor.lines_mut());
+            }
+        }
+
+        std::mem::replace(&mut self.line_ending, line_ending)
+    }
+
+    /// Method for getting whether the last line is terminated by a line ending
+    pub fn get_final_newline(&self) -> bool {
+        self.final_newline
+    }
+
+    /// ### Method for setting whether the last line is terminated by a line ending
+    ///
+    /// The replaced setting is returned.
+    pub fn set_final_newline(&mut self, final_newline: bool) -> bool {
+        std::mem::replace(&mut self.final_newline, final_newline)
+    }
+
+    /// Method for getting the separator character for list values
+    pub fn get_list_separator(&self) -> ListSeparator {
+        self.list_separator
+    }
+
+    /// ### Method for setting the separator character for list values
+    ///
+    /// The new separator is used by all list getters and setters of the groups in this [`KeyFile`]. Existing values
+    /// are *not* rewritten, i.e. lists that were written with a different separator need to be set again.
+    ///
+    /// The replaced separator is returned.
+    pub fn set_list_separator(&mut self, separator: ListSeparator) -> ListSeparator {
+        for group in self.groups.values_mut() {
+            group.list_separator = separator;
+        }
+
+        std::mem::replace(&mut self.list_separator, separator)
+    }
+
+    /// Method for getting the spans of the comments / empty lines after the last group in the source string (see
+    /// [`Source`])
+    pub fn decor_spans(&self, source: &Source) -> Vec<Option<Span>> {
+        self.decor.lines().iter().map(|line| source.span_of(line)).collect()
+    }
+
+    /// Method for getting the comments / empty lines after the last group
+    pub fn get_decor(&self) -> &[Cow<'a, str>] {
+        self.decor.lines()
+    }
+
+    /// ### Method for setting the comments / empty lines after the last group
+    ///
+    /// The replaced strings are returned.
+    pub fn set_decor<'d: 'a>(&mut self, decor: Decor<'d>) -> Vec<Cow<'a, str>> {
+        std::mem::replace(&mut self.decor, decor).into()
+    }
+
+    /// ### Method for removing the comments / empty lines after the last group
+    ///
+    /// See [`Group::take_decor`].
+    pub fn take_decor(&mut self) -> Decor<'a> {
+        std::mem::take(&mut self.decor)
+    }
+
+    /// Method for getting the spans of the comments / empty lines at the top of the file in the source string (see
+    /// [`Source`])
+    pub fn header_spans(&self, source: &Source) -> Vec<Option<Span>> {
+        self.header.lines().iter().map(|line| source.span_of(line)).collect()
+    }
+
+    /// ### Method for getting the comment at the top of the file, above a [`Group`], or above a [`KeyValuePair`]
+    ///
+    /// This method mirrors `g_key_file_get_comment` from GLib:
+    ///
+    /// - if `group` is [`None`], the comment at the top of the file is returned (and `key` is ignored)
+    /// - if `key` is [`None`], the comment above the header of the given group is returned
+    /// - otherwise, the comment above the given key is returned (translations are selected by including the locale
+    ///   specifier in the key, e.g. `Name[de]`)
+    ///
+    /// The comment text consists of the comment lines without their indentation and leading `#` character, joined
+    /// with `\n`. The comment above a group or key-value pair only consists of the contiguous comment lines directly
+    /// above it, while empty lines between comment lines are part of the comment at the top of the file. Other
+    /// preserved lines (like the invalid lines that are kept by [`KeyFile::parse_lenient`]) are never part of the
+    /// comment. If there is no comment line, [`None`] is returned.
+    ///
+    /// ```
+    /// use keyfile::KeyFile;
+    ///
+    /// let input = "# top comment\n\n# group comment\n[Desktop Entry]\n#key comment\n# continued\nName=Files\n";
+    /// let keyfile = KeyFile::parse(input).unwrap();
+    ///
+    /// let top = keyfile.get_comment(None, None).unwrap();
+    /// assert_eq!(top.as_deref(), Some(" top comment"));
+    ///
+    /// let group = keyfile.get_comment(Some("Desktop Entry"), None).unwrap();
+    /// assert_eq!(group.as_deref(), Some(" group comment"));
+    ///
+    /// let key = keyfile.get_comment(Some("Desktop Entry"), Some("Name")).unwrap();
+    /// assert_eq!(key.as_deref(), Some("key comment\n continued"));
+    /// ```
+    pub fn get_comment(&self, group: Option<&str>, key: Option<&str>) -> Result<Option<String>, CommentError> {
+        let decor = match group {
+            None => &self.header,
+            Some(name) => {
+                let group = self.groups.get(name).ok_or_else(|| CommentError::GroupNotFound {
+                    name: String::from(name),
+                })?;
+
+                match key {
+                    None => &group.decor,
+                    Some(key) => &group.get_compound(key)?.decor,
+                }
+            },
+        };
+
+        Ok(comment_text(decor, group.is_none()))
+    }
+
+    /// ### Method for setting the comment at the top of the file, above a [`Group`], or above a [`KeyValuePair`]
+    ///
+    /// This method mirrors `g_key_file_set_comment` from GLib, and the `group` and `key` arguments select the comment
+    /// like for [`KeyFile::get_comment`]. Every line of the comment text (separated by `\n` or `\r\n`) is written as a
+    /// separate comment line with a leading `#` character. These lines replace the existing comment lines (as selected
+    /// by [`KeyFile::get_comment`]), or are inserted directly above the item if there is no comment. All other lines
+    /// are kept in place. Passing [`None`] (or an empty string) removes the comment.
+    ///
+    /// The comment at the top of the file is separated from the first group by an empty line, which is written if
+    /// necessary (including for groups that are only inserted after setting the comment).
+    ///
+    /// ```
+    /// use keyfile::KeyFile;
+    ///
+    /// let mut keyfile = KeyFile::parse("[Desktop Entry]\n# old\nName=Files\n").unwrap();
+    ///
+    /// keyfile.set_comment(None, None, Some(" generated file")).unwrap();
+    /// keyfile.set_comment(Some("Desktop Entry"), Some("Name"), Some(" shown in menus\n (untranslated)")).unwrap();
+    /// assert_eq!(
+    ///     keyfile.to_string(),
+    ///     "# generated file\n\n[Desktop Entry]\n# shown in menus\n# (untranslated)\nName=Files\n"
+    /// );
+    ///
+    /// keyfile.set_comment(Some("Desktop Entry"), Some("Name"), None).unwrap();
+    /// assert_eq!(keyfile.to_string(), "# generated file\n\n[Desktop Entry]\nName=Files\n");
+    /// ```
+    ///
+    /// Comment texts that contain other control characters than tab and line breaks (including a `\r` character that
+    /// is not followed by `\n`) are rejected with [`CommentError::InvalidComment`].
+    pub fn set_comment(
+        &mut self,
+        group: Option<&str>,
+        key: Option<&str>,
+        comment: Option<&str>,
+    ) -> Result<(), CommentError> {
+        if let Some(span) = comment.and_then(invalid_comment_span) {
+            return Err(CommentError::InvalidComment { span });
+        }
+
+        let Some(name) = group else {
+            replace_comment(&mut self.header, comment, true);
+            return Ok(());
+        };
+
+        let group = self.groups.get_mut(name).ok_or_else(|| CommentError::GroupNotFound {
+            name: String::from(name),
+        })?;
+
+        match key {
+            None => replace_comment(&mut group.decor, comment, false),
+            Some(key) => replace_comment(&mut group.get_compound_mut(key)?.inner.decor, comment, false),
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Display for KeyFile<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut w = LineWriter::new(f, self.line_ending.as_str());
+
+        for line in self.header.lines() {
+            w.line(format_args!("{}", line))?;
+        }
+
+        // the lines at the top of the file would otherwise be parsed as the comment of the first group
+        if let Some((_, first)) = self.groups.first() {
+            let separated = first.decor.lines().first().map_or(false, |line| is_blank(line));
+            if self.header.lines().last().map_or(false, |line| !is_blank(line)) && !separated {
+                w.line(format_args!(""))?;
+            }
+        }
+
+        for (_name, group) in &self.groups {
+            group.write_lines(&mut w)?;
+        }
+
+        for line in self.decor.lines() {
+            w.line(format_args!("{}", line))?;
+        }
+
+        w.finish(self.final_newline)
+    }
+}
+
+impl<'a> Default for KeyFile<'a> {
+    fn default() -> Self {
+        KeyFile::new()
+    }
+}
+
+impl<'a> FromStr for KeyFile<'a> {
+    type Err = KeyFileError;
+
+    /// ### Parse a string into a [`KeyFile`]
+    ///
+    /// *Note*: This method performs a "deep copy" of all string references in order to return a [`KeyFile`] with a
+    /// `'static` lifetime. When this is not required, the [`KeyFile::parse`] method only performs zero-copy parsing,
     /// but returns a [`KeyFile`] whose lifetime is bound to the lifetime of the input string.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         KeyFile::parse(s).map(KeyFile::into_owned)
@@ -259,7 +1253,8 @@ impl<'a> FromStr for KeyFile<'a> {
 /// specifier to provide a translated version of a value for a given key.
 ///
 /// Any empty lines or comment lines ("decor") that precede the key-value pair are assumed to be associated with the
-/// key-value pair, and are preserved across edits. Whitespace around the `=` separator character is preserved as well.
+/// key-value pair, and are preserved across edits. Whitespace around the `=` separator character is preserved as well,
+/// and so is leading whitespace if it is accepted (see [`ParseOptions::glib_compat`]).
 #[derive(Clone, Debug, PartialEq)]
 pub struct KeyValuePair<'a> {
     pub(crate) key: Cow<'a, str>,
@@ -267,7 +1262,11 @@ pub struct KeyValuePair<'a> {
     pub(crate) value: Cow<'a, str>,
     pub(crate) wsl: Cow<'a, str>,
     pub(crate) wsr: Cow<'a, str>,
-    pub(crate) decor: Vec<Cow<'a, str>>,
+    pub(crate) decor: Decor<'a>,
+    // leading whitespace (only accepted when parsing with ParseOptions::glib_compat)
+    pub(crate) indent: Cow<'a, str>,
+    // carriage return at the end of the line that is not part of the line ending (in files with mixed line endings)
+    pub(crate) cr: bool,
 }
 
 impl<'a> KeyValuePair<'a> {
@@ -283,7 +1282,9 @@ impl<'a> KeyValuePair<'a> {
             value: value.into(),
             wsl: " ".into(),
             wsr: " ".into(),
-            decor: Vec::new(),
+            decor: Decor::default(),
+            indent: "".into(),
+            cr: false,
         }
     }
 
@@ -298,7 +1299,9 @@ impl<'a> KeyValuePair<'a> {
             value: value.into(),
             wsl: " ".into(),
             wsr: " ".into(),
-            decor: Vec::new(),
+            decor: Decor::default(),
+            indent: "".into(),
+            cr: false,
         }
     }
 
@@ -346,7 +1349,9 @@ impl<'a> KeyValuePair<'a> {
             value: value.into(),
             wsl: wsl.into(),
             wsr: wsr.into(),
-            decor: decor.into(),
+            decor,
+            indent: "".into(),
+            cr: false,
         }
     }
 
@@ -355,18 +1360,36 @@ impl<'a> KeyValuePair<'a> {
     /// This is a "deep copy" which converts any [`Cow::Borrowed`] into [`Cow::Owned`] by copying the underlying string
     /// into a new "owned" value.
     pub fn into_owned(self) -> KeyValuePair<'static> {
-        let owned_decor = self.decor.into_iter().map(|c| Cow::Owned(c.into_owned())).collect();
-
         KeyValuePair {
             key: Cow::Owned(self.key.into_owned()),
             locale: self.locale.map(Locale::into_owned),
             value: Cow::Owned(self.value.into_owned()),
             wsl: Cow::Owned(self.wsl.into_owned()),
             wsr: Cow::Owned(self.wsr.into_owned()),
-            decor: owned_decor,
+            decor: self.decor.into_owned(),
+            indent: Cow::Owned(self.indent.into_owned()),
+            cr: self.cr,
         }
     }
 
+    /// The line that represents this key-value pair in the file (without decor).
+    pub(crate) fn raw_line(&self) -> String {
+        format!(
+            "{}{}{}={}{}{}",
+            self.indent,
+            self.compound_key(),
+            self.wsl,
+            self.wsr,
+            self.value,
+            carriage_return(self.cr)
+        )
+    }
+
+    /// Key including the locale specifier (if any), as it is written in the file.
+    pub(crate) fn compound_key(&self) -> String {
+        compound_key(&self.key, self.locale.as_ref())
+    }
+
     /// Method for getting the key string
     pub fn get_key(&self) -> &str {
         &self.key
@@ -374,19 +1397,21 @@ impl<'a> KeyValuePair<'a> {
 
     /// ### Method for setting the key string
     ///
-    /// The replaced key string is returned.
-    pub fn set_key<'k: 'a>(&mut self, key: Key<'k>) -> Cow<str> {
+    /// The replaced key string is returned. To change the key of a key-value pair that is part of a [`Group`], use
+    /// [`Group::rename_key`] instead.
+    pub fn set_key<'k: 'a>(&mut self, key: Key<'k>) -> Cow<'a, str> {
         std::mem::replace(&mut self.key, key.into())
     }
 
     /// Method for getting the optional locale string
-    pub fn get_locale(&self) -> Option<&Locale> {
+    pub fn get_locale(&self) -> Option<&Locale<'a>> {
         self.locale.as_ref()
     }
 
     /// ### Method for setting the optional locale string
     ///
-    /// If this method replaces an existing locale string, it is returned.
+    /// If this method replaces an existing locale string, it is returned. To change the locale of a key-value pair that
+    /// is part of a [`Group`], use [`Group::change_locale`] instead.
     pub fn set_locale<'l: 'a>(&mut self, locale: Option<Locale<'l>>) -> Option<Locale<'a>> {
         std::mem::replace(&mut self.locale, locale)
     }
@@ -399,10 +1424,42 @@ impl<'a> KeyValuePair<'a> {
     /// ### Method for setting the value string
     ///
     /// The replaced value string is returned.
-    pub fn set_value<'v: 'a>(&mut self, value: Value<'v>) -> Cow<str> {
+    pub fn set_value<'v: 'a>(&mut self, value: Value<'v>) -> Cow<'a, str> {
         std::mem::replace(&mut self.value, value.into())
     }
 
+    /// ### Method for getting the value string with escape sequences decoded
+    ///
+    /// The escape sequences `\s`, `\n`, `\t`, `\r`, and `\\` are replaced with the characters they represent, which
+    /// matches the behaviour of `g_key_file_get_string`. If the value does not contain any escape sequences, the
+    /// returned string is borrowed from the raw value.
+    ///
+    /// ```
+    /// use keyfile::{types::*, KeyValuePair};
+    ///
+    /// let kv = KeyValuePair::new(
+    ///     Key::try_from("Comment").unwrap(),
+    ///     Value::try_from(r"First line\nSecond line").unwrap(),
+    /// );
+    ///
+    /// assert_eq!(kv.get_string().unwrap(), "First line\nSecond line");
+    /// ```
+    pub fn get_string(&self) -> Result<Cow<'_, str>, ValueError> {
+        unescape(&self.value)
+    }
+
+    /// ### Method for setting the value string from an unescaped string
+    ///
+    /// Newlines, tabs, carriage returns, backslashes, and leading spaces are escaped before the value is stored. If
+    /// the escaped string is still not a valid [`Value`] (i.e. it contains other control characters), an error is
+    /// returned and the key-value pair is not modified.
+    ///
+    /// The replaced value string is returned.
+    pub fn set_string<'v: 'a>(&mut self, value: &'v str) -> Result<Cow<'a, str>, InvalidString> {
+        let value = Value::try_from(escape(value))?;
+        Ok(self.set_value(value))
+    }
+
     /// Method for getting the whitespace surrounding the `=` separator
     pub fn get_whitespace(&self) -> (&str, &str) {
         (&self.wsl, &self.wsr)
@@ -411,7 +1468,7 @@ impl<'a> KeyValuePair<'a> {
     /// ### Method for setting the whitespace surrounding the `=` separator
     ///
     /// The replaced strings are returned.
-    pub fn set_whitespace<'w: 'a>(&mut self, wsl: Whitespace<'w>, wsr: Whitespace<'w>) -> (Cow<str>, Cow<str>) {
+    pub fn set_whitespace<'w: 'a>(&mut self, wsl: Whitespace<'w>, wsr: Whitespace<'w>) -> (Cow<'a, str>, Cow<'a, str>) {
         (
             std::mem::replace(&mut self.wsl, wsl.into()),
             std::mem::replace(&mut self.wsr, wsr.into()),
@@ -419,31 +1476,191 @@ impl<'a> KeyValuePair<'a> {
     }
 
     /// Method for getting the comments / empty lines preceding the [`KeyValuePair`]
-    pub fn get_decor(&self) -> &[Cow<str>] {
-        self.decor.as_slice()
+    pub fn get_decor(&self) -> &[Cow<'a, str>] {
+        self.decor.lines()
     }
 
     /// ### Method for setting the commens / empty lines preceding the [`KeyValuePair`]
     ///
     /// The replaced strings are returned.
-    pub fn set_decor<'d: 'a>(&mut self, decor: Decor<'d>) -> Vec<Cow<str>> {
-        std::mem::replace(&mut self.decor, decor.into())
+    pub fn set_decor<'d: 'a>(&mut self, decor: Decor<'d>) -> Vec<Cow<'a, str>> {
+        std::mem::replace(&mut self.decor, decor).into()
+    }
+
+    /// ### Method for removing the comments / empty lines preceding the [`KeyValuePair`]
+    ///
+    /// See [`Group::take_decor`].
+    pub fn take_decor(&mut self) -> Decor<'a> {
+        std::mem::take(&mut self.decor)
+    }
+
+    /// ### Method for getting the text of the comment preceding the [`KeyValuePair`]
+    ///
+    /// See [`KeyFile::get_comment`] for how the comment text is derived from the comment lines.
+    pub fn get_comment(&self) -> Option<String> {
+        comment_text(&self.decor, false)
+    }
+
+    /// ### Method for setting the text of the comment preceding the [`KeyValuePair`]
+    ///
+    /// See [`KeyFile::set_comment`] for how the comment text is written as comment lines, and which comment texts are
+    /// rejected as invalid.
+    pub fn set_comment(&mut self, comment: Option<&str>) -> Result<(), InvalidString> {
+        if let Some(span) = comment.and_then(invalid_comment_span) {
+            return Err(InvalidString::Comment { span });
+        }
+
+        replace_comment(&mut self.decor, comment, false);
+        Ok(())
+    }
+
+    /// ### Method for getting the span of the whole line of the [`KeyValuePair`] in the source string
+    ///
+    /// The span starts at the key (or the indentation, if there is any) and ends after the value. If the key or the
+    /// value were modified after parsing, then [`None`] is returned (see [`Source`]).
+    pub fn span(&self, source: &Source) -> Option<Span> {
+        let start = match source.span_of(&self.indent) {
+            Some(indent) if !self.indent.is_empty() => indent,
+            _ => self.key_span(source)?,
+        };
+        Some(start.to(self.value_span(source)?))
+    }
+
+    /// Method for getting the span of the key string in the source string (see [`Source`])
+    pub fn key_span(&self, source: &Source) -> Option<Span> {
+        source.span_of(&self.key)
+    }
+
+    /// ### Method for getting the span of the locale specifier in the source string (see [`Source`])
+    ///
+    /// The span does not include the surrounding `[` and `]` characters.
+    pub fn locale_span(&self, source: &Source) -> Option<Span> {
+        let locale = self.locale.as_ref()?;
+        let start = source.span_of(&locale.lang)?;
+
+        let last = [&locale.modifier, &locale.encoding, &locale.country]
+            .into_iter()
+            .find_map(Option::as_ref)
+            .unwrap_or(&locale.lang);
+
+        Some(start.to(source.span_of(last)?))
+    }
+
+    /// Method for getting the span of the value string in the source string (see [`Source`])
+    pub fn value_span(&self, source: &Source) -> Option<Span> {
+        source.span_of(&self.value)
+    }
+
+    /// Method for getting the spans of the comments / empty lines preceding the [`KeyValuePair`] in the source string
+    /// (see [`Source`])
+    pub fn decor_spans(&self, source: &Source) -> Vec<Option<Span>> {
+        self.decor.lines().iter().map(|line| source.span_of(line)).collect()
     }
 }
 
-impl<'a> Display for KeyValuePair<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for line in &self.decor {
-            writeln!(f, "{}", line)?;
+impl<'a> KeyValuePair<'a> {
+    pub(crate) fn write_lines(&self, w: &mut LineWriter) -> fmt::Result {
+        for line in self.decor.lines() {
+            w.line(format_args!("{}", line))?;
         }
 
         if let Some(locale) = &self.locale {
-            write!(f, "{}[{}]{}={}{}", self.key, locale, self.wsl, self.wsr, self.value)?;
+            w.line(format_args!(
+                "{}{}[{}]{}={}{}{}",
+                self.indent,
+                self.key,
+                locale,
+                self.wsl,
+                self.wsr,
+                self.value,
+                carriage_return(self.cr)
+            ))
         } else {
-            write!(f, "{}{}={}{}", self.key, self.wsl, self.wsr, self.value)?;
+            w.line(format_args!(
+                "{}{}{}={}{}{}",
+                self.indent,
+                self.key,
+                self.wsl,
+                self.wsr,
+                self.value,
+                carriage_return(self.cr)
+            ))
         }
+    }
+}
 
-        Ok(())
+impl<'a> Display for KeyValuePair<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut w = LineWriter::new(f, "\n");
+        self.write_lines(&mut w)?;
+        w.finish(false)
+    }
+}
+
+/// ## Mutable access to a [`KeyValuePair`] in a [`Group`]
+///
+/// This type is returned by [`Group::get_mut`]. It provides read access to all fields of the key-value pair (via
+/// [`Deref`]), but only allows changing the fields that are not part of the key that is used to look up the key-value
+/// pair in the [`Group`], i.e. it does not allow changing the key or locale (see [`Group::rename_key`] and
+/// [`Group::change_locale`]).
+#[derive(Debug)]
+pub struct KeyValuePairMut<'g, 'a> {
+    inner: &'g mut KeyValuePair<'a>,
+}
+
+impl<'g, 'a> KeyValuePairMut<'g, 'a> {
+    pub(crate) fn new(inner: &'g mut KeyValuePair<'a>) -> Self {
+        KeyValuePairMut { inner }
+    }
+
+    /// ### Method for setting the value string
+    ///
+    /// See [`KeyValuePair::set_value`].
+    pub fn set_value<'v: 'a>(&mut self, value: Value<'v>) -> Cow<'a, str> {
+        self.inner.set_value(value)
+    }
+
+    /// ### Method for setting the value string from an unescaped string
+    ///
+    /// See [`KeyValuePair::set_string`].
+    pub fn set_string<'v: 'a>(&mut self, value: &'v str) -> Result<Cow<'a, str>, InvalidString> {
+        self.inner.set_string(value)
+    }
+
+    /// ### Method for setting the whitespace surrounding the `=` separator
+    ///
+    /// See [`KeyValuePair::set_whitespace`].
+    pub fn set_whitespace<'w: 'a>(&mut self, wsl: Whitespace<'w>, wsr: Whitespace<'w>) -> (Cow<'a, str>, Cow<'a, str>) {
+        self.inner.set_whitespace(wsl, wsr)
+    }
+
+    /// ### Method for setting the comments / empty lines preceding the [`KeyValuePair`]
+    ///
+    /// See [`KeyValuePair::set_decor`].
+    pub fn set_decor<'d: 'a>(&mut self, decor: Decor<'d>) -> Vec<Cow<'a, str>> {
+        self.inner.set_decor(decor)
+    }
+
+    /// ### Method for removing the comments / empty lines preceding the [`KeyValuePair`]
+    ///
+    /// See [`KeyValuePair::take_decor`].
+    pub fn take_decor(&mut self) -> Decor<'a> {
+        self.inner.take_decor()
+    }
+
+    /// ### Method for setting the text of the comment preceding the [`KeyValuePair`]
+    ///
+    /// See [`KeyValuePair::set_comment`].
+    pub fn set_comment(&mut self, comment: Option<&str>) -> Result<(), InvalidString> {
+        self.inner.set_comment(comment)
+    }
+}
+
+impl<'g, 'a> Deref for KeyValuePairMut<'g, 'a> {
+    type Target = KeyValuePair<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        self.inner
     }
 }
 
@@ -454,12 +1671,23 @@ impl<'a> Display for KeyValuePair<'a> {
 /// another group header or the end of the string.
 ///
 /// Any empty lines or comment lines ("decor") that precede the opening group header are assumed to be associated with
-/// the group as well, and are preserved across edits
+/// the group as well, and are preserved across edits. Lines that were preserved verbatim by
+/// [`KeyFile::parse_lenient`] after the last key-value pair of a group are kept at the end of the group, which also
+/// applies to comments and empty lines if [`TrailingDecor::PreviousGroup`] is used (see
+/// [`ParseOptions::trailing_decor`]).
 #[derive(Clone, Debug)]
 pub struct Group<'a> {
     pub(crate) name: Cow<'a, str>,
     pub(crate) entries: IndexMap<(Cow<'a, str>, Option<Locale<'a>>), KeyValuePair<'a>>,
-    pub(crate) decor: Vec<Cow<'a, str>>,
+    pub(crate) decor: Decor<'a>,
+    pub(crate) trailing: Decor<'a>,
+    // leading whitespace of the group header (only accepted when parsing with ParseOptions::glib_compat)
+    pub(crate) indent: Cow<'a, str>,
+    // whitespace after the group header (only accepted when parsing with ParseOptions::glib_compat)
+    pub(crate) ws: Cow<'a, str>,
+    // carriage return at the end of the group header that is not part of the line ending (see KeyValuePair)
+    pub(crate) cr: bool,
+    pub(crate) list_separator: ListSeparator,
 }
 
 impl<'a> Group<'a> {
@@ -471,7 +1699,12 @@ impl<'a> Group<'a> {
         Group {
             name: name.into(),
             entries: IndexMap::new(),
-            decor: Vec::new(),
+            decor: Decor::default(),
+            trailing: Decor::default(),
+            indent: "".into(),
+            ws: "".into(),
+            cr: false,
+            list_separator: ListSeparator::default(),
         }
     }
 
@@ -479,14 +1712,25 @@ impl<'a> Group<'a> {
         name: GroupName<'e>,
         entries: IndexMap<(Cow<'e, str>, Option<Locale<'e>>), KeyValuePair<'e>>,
         decor: Decor<'e>,
+        list_separator: ListSeparator,
     ) -> Self {
         Group {
             name: name.into(),
             entries,
-            decor: decor.into(),
+            decor,
+            trailing: Decor::default(),
+            indent: "".into(),
+            ws: "".into(),
+            cr: false,
+            list_separator,
         }
     }
 
+    /// Method for getting the name of the [`Group`]
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
     /// ### Method for converting a `Group<'a>` into a `Group<'static>`
     ///
     /// This is a "deep copy" which converts any [`Cow::Borrowed`] into [`Cow::Owned`] by copying the
@@ -495,65 +1739,1967 @@ impl<'a> Group<'a> {
         let owned_name: Cow<'static, str> = Cow::Owned(self.name.into_owned());
 
         let mut owned = Group::new(GroupName::new_unchecked(owned_name.clone()));
+        owned.list_separator = self.list_separator;
+        owned.indent = Cow::Owned(self.indent.into_owned());
+        owned.ws = Cow::Owned(self.ws.into_owned());
+        owned.cr = self.cr;
 
         for (_key, kv) in self.entries {
             owned.insert(kv.into_owned());
         }
 
-        for line in self.decor {
-            owned.decor.push(Cow::Owned(line.into_owned()));
-        }
+        owned.decor = self.decor.into_owned();
+        owned.trailing = self.trailing.into_owned();
 
         owned
     }
 
-    /// ### Method for getting a reference to the [`KeyValuePair`] associated with the given key
+    /// ### Method for iterating over the key-value pairs of the [`Group`]
     ///
-    /// If there is no key-value pair associated with the given key, then [`None`] is returned.
-    pub fn get<'k: 'a>(&self, key: &'k str, locale: Option<Locale<'k>>) -> Option<&KeyValuePair> {
-        self.entries.get(&(key.into(), locale))
+    /// Key-value pairs (including translations) are returned in the order in which they appear in the file.
+    ///
+    /// ```
+    /// use keyfile::KeyFile;
+    ///
+    /// let input = "[Desktop Entry]\nName=Files\nName[de]=Dateien\nIcon=files\nName[fr]=Fichiers\n";
+    /// let keyfile = KeyFile::parse(input).unwrap();
+    /// let group = keyfile.get_group("Desktop Entry").unwrap();
+    ///
+    /// let entries: Vec<&str> = group.entries().map(|kv| kv.get_key()).collect();
+    /// assert_eq!(entries, ["Name", "Name", "Icon", "Name"]);
+    ///
+    /// let keys: Vec<&str> = group.keys().collect();
+    /// assert_eq!(keys, ["Name", "Icon"]);
+    ///
+    /// let translations: Vec<(String, &str)> = group
+    ///     .translations("Name")
+    ///     .map(|(locale, value)| (locale.to_string(), value))
+    ///     .collect();
+    /// assert_eq!(translations, [("de".to_string(), "Dateien"), ("fr".to_string(), "Fichiers")]);
+    /// ```
+    pub fn entries(&self) -> impl Iterator<Item = &KeyValuePair<'a>> {
+        self.entries.values()
     }
 
-    /// ### Method for getting a mutable reference to the [`KeyValuePair`] associated with the given key
+    /// ### Method for iterating over mutable access to the key-value pairs of the [`Group`]
     ///
-    /// If there is no key-value pair associated with the given key, then [`None`] is returned.
-    pub fn get_mut<'k: 'a>(&'a mut self, key: &'k str, locale: Option<Locale<'k>>) -> Option<&'a mut KeyValuePair<'a>> {
-        self.entries.get_mut(&(key.into(), locale))
+    /// See [`Group::entries`] and [`KeyValuePairMut`].
+    pub fn entries_mut(&mut self) -> impl Iterator<Item = KeyValuePairMut<'_, 'a>> {
+        self.entries.values_mut().map(KeyValuePairMut::new)
     }
 
-    /// ### Method for inserting a new [`KeyValuePair`] into the [`Group`]
+    /// ### Method for iterating over the distinct keys of the [`Group`]
     ///
-    /// The key-value pair will be appended as the last entry in the [`Group`].
+    /// Every key is returned once without a locale specifier, in the order of its first occurrence, even if the group
+    /// only contains translations of the key (like `g_key_file_get_keys`, which also returns keys with a locale
+    /// specifier, though).
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        let mut seen = HashSet::new();
+        self.entries
+            .keys()
+            .map(|(key, _)| key.as_ref())
+            .filter(move |key| seen.insert(*key))
+    }
+
+    /// ### Method for iterating over the translations of the given key
     ///
-    /// Inserting a key-value pair with the same key as an already existing key-value pair will
-    /// replace the existing key-value pair. In this case, the replaced value is returned.
-    pub fn insert<'kv: 'a>(&mut self, kv: KeyValuePair<'kv>) -> Option<KeyValuePair> {
-        // This clone is cheap only if the kv.key is a Cow::Borrowed(&str).
-        // If kv.key is a Cow::Owned(String), the String needs to be copied.
-        self.entries.insert((kv.key.clone(), kv.locale.clone()), kv)
+    /// The locale and the value of every key-value pair with the given key and a locale specifier are returned in the
+    /// order in which they appear in the file. Escape sequences in the values are not decoded (see
+    /// [`KeyValuePair::get_string`]).
+    pub fn translations<'s>(&'s self, key: &'s str) -> impl Iterator<Item = (&'s Locale<'a>, &'s str)> {
+        self.entries
+            .values()
+            .filter(move |kv| kv.key == key)
+            .filter_map(|kv| Some((kv.locale.as_ref()?, kv.value.as_ref())))
     }
 
-    /// ### Method for removing a [`KeyValuePair`] associated with the given key
+    /// ### Method for getting a reference to the [`KeyValuePair`] associated with the given key
     ///
     /// If there is no key-value pair associated with the given key, then [`None`] is returned.
+    pub fn get(&self, key: &str, locale: Option<&Locale>) -> Option<&KeyValuePair<'a>> {
+        self.entries.get(&EntryQuery { key, locale })
+    }
+
+    /// ### Method for getting mutable access to the [`KeyValuePair`] associated with the given key
     ///
-    /// This operation preserves the order of the remaining key-value pairs.
-    pub fn remove<'k: 'a>(&mut self, key: &'k str, locale: Option<Locale<'k>>) -> Option<KeyValuePair> {
-        self.entries.shift_remove(&(key.into(), locale))
+    /// If there is no key-value pair associated with the given key, then [`None`] is returned.
+    ///
+    /// The returned [`KeyValuePairMut`] does not allow changing the key or locale of the key-value pair, since the
+    /// [`Group`] needs to know about such changes. Use [`Group::rename_key`] and [`Group::change_locale`] instead.
+    ///
+    /// ```
+    /// use keyfile::{types::Locale, KeyFile};
+    ///
+    /// let mut keyfile = KeyFile::parse("[Desktop Entry]\nName=Files\nName[de]=Dateien\n").unwrap();
+    /// let de = Locale::try_from("de").unwrap();
+    ///
+    /// let group = keyfile.get_group_mut("Desktop Entry").unwrap();
+    /// group.get_mut("Name", None).unwrap().set_string("Nautilus").unwrap();
+    /// group.get_mut("Name", Some(&de)).unwrap().set_string("Nautilus").unwrap();
+    ///
+    /// let group = keyfile.get_group("Desktop Entry").unwrap();
+    /// assert_eq!(group.get("Name", Some(&de)).unwrap().get_value(), "Nautilus");
+    /// ```
+    pub fn get_mut(&mut self, key: &str, locale: Option<&Locale>) -> Option<KeyValuePairMut<'_, 'a>> {
+        self.entries
+            .get_mut(&EntryQuery { key, locale })
+            .map(KeyValuePairMut::new)
     }
-}
 
-impl<'a> Display for Group<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for line in &self.decor {
-            writeln!(f, "{}", line)?;
-        }
-        writeln!(f, "[{}]", self.name)?;
+    /// ### Method for getting the entry for the [`KeyValuePair`] with the given key and locale for in-place manipulation
+    ///
+    /// See [`Entry`] for an example.
+    pub fn entry<'k: 'a>(&mut self, key: Key<'k>, locale: Option<Locale<'k>>) -> Entry<'_, 'a> {
+        let key: Cow<'a, str> = key.into();
+        let position = self.translation_position(&key, locale.as_ref());
+        Entry::new(self.entries.entry((key, locale)), position)
+    }
 
-        for kv in self.entries.values() {
-            writeln!(f, "{}", kv)?;
-        }
+    /// ### Method for changing the key of the [`KeyValuePair`] associated with the given key and locale
+    ///
+    /// The key-value pair keeps its position in the [`Group`]. Translations of the key-value pair are separate entries,
+    /// and are not renamed.
+    ///
+    /// If there is no key-value pair associated with the given key and locale, or if there already is a different
+    /// key-value pair with the new key and the same locale, then an error is returned and the [`Group`] is not
+    /// modified.
+    ///
+    /// ```
+    /// use keyfile::{types::Key, KeyFile, RekeyError};
+    ///
+    /// let mut keyfile = KeyFile::parse("[Desktop Entry]\nName=Files\nIcon=files\n").unwrap();
+    /// let mut group = keyfile.remove_group("Desktop Entry").unwrap();
+    ///
+    /// group.rename_key("Name", None, Key::try_from("GenericName").unwrap()).unwrap();
+    /// assert_eq!(group.get_string("GenericName").unwrap().unwrap(), "Files");
+    /// assert_eq!(group.to_string(), "[Desktop Entry]\nGenericName=Files\nIcon=files\n");
+    ///
+    /// let error = group.rename_key("Icon", None, Key::try_from("GenericName").unwrap()).unwrap_err();
+    /// assert!(matches!(error, RekeyError::Collision { .. }));
+    /// ```
+    pub fn rename_key<'k: 'a>(
+        &mut self,
+        key: &str,
+        locale: Option<&Locale>,
+        new_key: Key<'k>,
+    ) -> Result<(), RekeyError> {
+        let new_key: Cow<'a, str> = new_key.into();
+        self.rekey(key, locale, &new_key, locale, |kv| kv.key = new_key.clone())
+    }
 
-        Ok(())
+    /// ### Method for changing the locale of the [`KeyValuePair`] associated with the given key and locale
+    ///
+    /// The key-value pair keeps its position in the [`Group`]. Passing [`None`] as the new locale turns a translation
+    /// into the untranslated key-value pair, and vice versa.
+    ///
+    /// If there is no key-value pair associated with the given key and locale, or if there already is a different
+    /// key-value pair with the same key and the new locale, then an error is returned and the [`Group`] is not
+    /// modified.
+    pub fn change_locale<'l: 'a>(
+        &mut self,
+        key: &str,
+        locale: Option<&Locale>,
+        new_locale: Option<Locale<'l>>,
+    ) -> Result<(), RekeyError> {
+        let new_locale: Option<Locale<'a>> = new_locale;
+        self.rekey(key, locale, key, new_locale.as_ref(), |kv| {
+            kv.locale = new_locale.clone()
+        })
+    }
+
+    /// ### Method for getting the value associated with the given key as a string
+    ///
+    /// Escape sequences in the value are decoded (see [`KeyValuePair::get_string`]).
+    ///
+    /// If there is no key-value pair associated with the given key, then [`None`] is returned.
+    pub fn get_string(&self, key: &str) -> Result<Option<Cow<'_, str>>, InvalidValue> {
+        self.get_with(key, unescape)
+    }
+
+    /// ### Method for setting the value associated with the given key to a string
+    ///
+    /// The string is escaped before it is stored (see [`KeyValuePair::set_string`]). If a key-value pair is already
+    /// associated with the given key, only its value is replaced, and the surrounding whitespace and decor are
+    /// preserved. Otherwise, a new key-value pair is appended to the [`Group`].
+    pub fn set_string<'k: 'a>(&mut self, key: Key<'k>, value: &'k str) -> Result<(), InvalidString> {
+        let value = Value::try_from(escape(value))?;
+        self.set(key, None, value);
+        Ok(())
+    }
+
+    /// ### Method for setting the translation of the value associated with the given key for the given locale
+    ///
+    /// This method is equivalent to [`Group::set_string`] except that the key-value pair for the given locale is
+    /// modified. A new translation is inserted right after the untranslated key-value pair and the existing
+    /// translations of the same key (or appended to the [`Group`] if there are none).
+    ///
+    /// ```
+    /// use keyfile::{types::*, KeyFile};
+    ///
+    /// let mut keyfile = KeyFile::parse("[Desktop Entry]\nName=Files\nIcon=files\n").unwrap();
+    /// let group = keyfile.get_group_mut("Desktop Entry").unwrap();
+    ///
+    /// group
+    ///     .set_locale_string(Key::try_from("Name").unwrap(), Locale::try_from("de").unwrap(), "Dateien")
+    ///     .unwrap();
+    /// assert_eq!(group.to_string(), "[Desktop Entry]\nName=Files\nName[de] = Dateien\nIcon=files\n");
+    /// ```
+    pub fn set_locale_string<'k: 'a>(
+        &mut self,
+        key: Key<'k>,
+        locale: Locale<'k>,
+        value: &'k str,
+    ) -> Result<(), InvalidString> {
+        let value = Value::try_from(escape(value))?;
+        self.set(key, Some(locale), value);
+        Ok(())
+    }
+
+    /// ### Method for getting the [`KeyValuePair`] with the best translation of a key for the given locale
+    ///
+    /// This method implements the matching rules for localized values from the Desktop Entry Specification (see
+    /// [`Locale::fallbacks`]). For a locale of the form `lang_COUNTRY@MODIFIER`, the key-value pairs for
+    /// `lang_COUNTRY@MODIFIER`, `lang_COUNTRY`, `lang@MODIFIER`, and `lang` are tried in this order, and the untranslated
+    /// key-value pair is used as the last fallback. The encoding of locales is ignored for matching.
+    ///
+    /// The locale that was actually matched can be obtained with [`KeyValuePair::get_locale`] on the returned value.
+    ///
+    /// If there is no matching key-value pair, then [`None`] is returned.
+    pub fn get_localized(&self, key: &str, locale: &Locale) -> Option<&KeyValuePair<'a>> {
+        self.get_best_localized(key, std::slice::from_ref(locale))
+    }
+
+    /// ### Method for getting the [`KeyValuePair`] with the best translation of a key for a list of locales
+    ///
+    /// The locales are tried in order of preference, and the matching rules from [`Group::get_localized`] are applied
+    /// to each of them. The untranslated key-value pair is only used if there is no translation for *any* of the given
+    /// locales. This supports priority lists like `LANGUAGE=sr@latin:de:en` (see [`Locale::from_env_vars`]).
+    ///
+    /// If there is no matching key-value pair, then [`None`] is returned.
+    pub fn get_best_localized(&self, key: &str, preferences: &[Locale]) -> Option<&KeyValuePair<'a>> {
+        preferences
+            .iter()
+            .flat_map(|locale| locale.fallbacks())
+            .find_map(|candidate| self.find_translation(key, &candidate))
+            .or_else(|| self.get(key, None))
+    }
+
+    /// ### Method for getting the best translation of the value associated with the given key as a string
+    ///
+    /// The key-value pair is looked up with the same rules as [`Group::get_localized`], and escape sequences in its
+    /// value are decoded. The value is returned together with the locale that was actually matched, which is [`None`]
+    /// if the value of the untranslated key-value pair is returned.
+    ///
+    /// If there is no matching key-value pair, then [`None`] is returned.
+    ///
+    /// ```
+    /// use keyfile::{types::Locale, KeyFile};
+    ///
+    /// let keyfile = KeyFile::parse("[Desktop Entry]\nName=Files\nName[de]=Dateien\n").unwrap();
+    /// let group = keyfile.get_group("Desktop Entry").unwrap();
+    ///
+    /// let (name, matched) = group
+    ///     .get_locale_string("Name", &Locale::try_from("de_AT").unwrap())
+    ///     .unwrap()
+    ///     .unwrap();
+    /// assert_eq!(name, "Dateien");
+    /// assert_eq!(matched, Some(&Locale::try_from("de").unwrap()));
+    ///
+    /// let (name, matched) = group
+    ///     .get_locale_string("Name", &Locale::try_from("fr").unwrap())
+    ///     .unwrap()
+    ///     .unwrap();
+    /// assert_eq!(name, "Files");
+    /// assert_eq!(matched, None);
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn get_locale_string(
+        &self,
+        key: &str,
+        locale: &Locale,
+    ) -> Result<Option<(Cow<'_, str>, Option<&Locale<'a>>)>, InvalidValue> {
+        self.get_localized_with(self.get_localized(key, locale), unescape)
+    }
+
+    /// ### Method for getting the best translation of the value associated with the given key as a list of strings
+    ///
+    /// The key-value pair is looked up with the same rules as [`Group::get_localized`], and its value is split into a
+    /// list in the same way as in [`Group::get_string_list`]. The list is returned together with the locale that was
+    /// actually matched, which is [`None`] if the value of the untranslated key-value pair is returned.
+    ///
+    /// If there is no matching key-value pair, then [`None`] is returned.
+    #[allow(clippy::type_complexity)]
+    pub fn get_locale_string_list(
+        &self,
+        key: &str,
+        locale: &Locale,
+    ) -> Result<Option<(Vec<Cow<'_, str>>, Option<&Locale<'a>>)>, InvalidValue> {
+        self.get_localized_with(self.get_localized(key, locale), |value| {
+            split_list(value, self.list_separator.into())
+        })
+    }
+
+    /// ### Method for getting the best translation of the value associated with the given key for a list of locales
+    ///
+    /// The key-value pair is looked up with the same rules as [`Group::get_best_localized`], and escape sequences in
+    /// its value are decoded. The value is returned together with the locale that was actually matched, which is
+    /// [`None`] if the value of the untranslated key-value pair is returned.
+    ///
+    /// If there is no matching key-value pair, then [`None`] is returned.
+    ///
+    /// ```
+    /// use keyfile::{types::Locale, KeyFile};
+    /// use std::collections::HashMap;
+    ///
+    /// let keyfile = KeyFile::parse("[Desktop Entry]\nName=Files\nName[de]=Dateien\nName[sr]=Датотеке\n").unwrap();
+    /// let group = keyfile.get_group("Desktop Entry").unwrap();
+    ///
+    /// let vars = HashMap::from([(String::from("LANGUAGE"), String::from("sr@latin:de:en"))]);
+    /// let preferences = Locale::from_env_vars(&vars);
+    ///
+    /// let (name, matched) = group.get_best_locale_string("Name", &preferences).unwrap().unwrap();
+    /// assert_eq!(name, "Датотеке");
+    /// assert_eq!(matched, Some(&Locale::try_from("sr").unwrap()));
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn get_best_locale_string(
+        &self,
+        key: &str,
+        preferences: &[Locale],
+    ) -> Result<Option<(Cow<'_, str>, Option<&Locale<'a>>)>, InvalidValue> {
+        self.get_localized_with(self.get_best_localized(key, preferences), unescape)
+    }
+
+    /// ### Method for getting the best translation of a list value for a list of locales
+    ///
+    /// The key-value pair is looked up with the same rules as [`Group::get_best_localized`], and its value is split
+    /// into a list in the same way as in [`Group::get_string_list`]. The list is returned together with the locale that
+    /// was actually matched, which is [`None`] if the value of the untranslated key-value pair is returned.
+    ///
+    /// If there is no matching key-value pair, then [`None`] is returned.
+    #[allow(clippy::type_complexity)]
+    pub fn get_best_locale_string_list(
+        &self,
+        key: &str,
+        preferences: &[Locale],
+    ) -> Result<Option<(Vec<Cow<'_, str>>, Option<&Locale<'a>>)>, InvalidValue> {
+        self.get_localized_with(self.get_best_localized(key, preferences), |value| {
+            split_list(value, self.list_separator.into())
+        })
+    }
+
+    /// ### Method for getting the value associated with the given key as a boolean
+    ///
+    /// The values `true` and `1` are interpreted as `true`, and the values `false` and `0` are interpreted as
+    /// `false`. Trailing whitespace is ignored. This matches the behaviour of `g_key_file_get_boolean`.
+    ///
+    /// If there is no key-value pair associated with the given key, then [`None`] is returned.
+    ///
+    /// ```
+    /// use keyfile::KeyFile;
+    ///
+    /// let keyfile = KeyFile::parse("[Desktop Entry]\nTerminal=false\nHidden=yes\n").unwrap();
+    /// let group = keyfile.get_group("Desktop Entry").unwrap();
+    ///
+    /// assert_eq!(group.get_boolean("Terminal").unwrap(), Some(false));
+    /// assert_eq!(group.get_boolean("NoDisplay").unwrap(), None);
+    /// assert!(group.get_boolean("Hidden").is_err());
+    /// ```
+    pub fn get_boolean(&self, key: &str) -> Result<Option<bool>, InvalidValue> {
+        self.get_with(key, parse_as_boolean)
+    }
+
+    /// ### Method for getting the value associated with the given key as a 32-bit signed integer
+    ///
+    /// Leading whitespace is ignored, and the number may be followed by whitespace and any other characters, which are
+    /// ignored as well (e.g. `4 2` is interpreted as `4`). This matches the behaviour of `g_key_file_get_integer`.
+    ///
+    /// If there is no key-value pair associated with the given key, then [`None`] is returned.
+    pub fn get_integer(&self, key: &str) -> Result<Option<i32>, InvalidValue> {
+        self.get_with(key, parse_as_integer)
+    }
+
+    /// ### Method for getting the value associated with the given key as a 64-bit signed integer
+    ///
+    /// Leading whitespace is ignored, but trailing whitespace is not. This matches the behaviour of
+    /// `g_key_file_get_int64`.
+    ///
+    /// If there is no key-value pair associated with the given key, then [`None`] is returned.
+    pub fn get_int64(&self, key: &str) -> Result<Option<i64>, InvalidValue> {
+        self.get_with(key, parse_as_int64)
+    }
+
+    /// ### Method for getting the value associated with the given key as a 64-bit unsigned integer
+    ///
+    /// Leading whitespace is ignored, but trailing whitespace is not. This matches the behaviour of
+    /// `g_key_file_get_uint64`, except that negative numbers are rejected instead of wrapping around.
+    ///
+    /// If there is no key-value pair associated with the given key, then [`None`] is returned.
+    pub fn get_uint64(&self, key: &str) -> Result<Option<u64>, InvalidValue> {
+        self.get_with(key, parse_as_uint64)
+    }
+
+    /// ### Method for getting the value associated with the given key as a double-precision floating-point number
+    ///
+    /// Leading whitespace is ignored, but trailing whitespace is not. The decimal separator is always `.`,
+    /// independent of the current locale. This matches the behaviour of `g_key_file_get_double`.
+    ///
+    /// If there is no key-value pair associated with the given key, then [`None`] is returned.
+    pub fn get_double(&self, key: &str) -> Result<Option<f64>, InvalidValue> {
+        self.get_with(key, parse_as_double)
+    }
+
+    /// ### Method for setting the value associated with the given key to a boolean
+    ///
+    /// If a key-value pair is already associated with the given key, only its value is replaced, and the surrounding
+    /// whitespace and decor are preserved. Otherwise, a new key-value pair is appended to the [`Group`].
+    pub fn set_boolean<'k: 'a>(&mut self, key: Key<'k>, value: bool) {
+        self.set(key, None, Value::from(value));
+    }
+
+    /// ### Method for setting the value associated with the given key to a 32-bit signed integer
+    ///
+    /// See [`Group::set_boolean`] for how existing key-value pairs are handled.
+    pub fn set_integer<'k: 'a>(&mut self, key: Key<'k>, value: i32) {
+        self.set(key, None, Value::from(value));
+    }
+
+    /// ### Method for setting the value associated with the given key to a 64-bit signed integer
+    ///
+    /// See [`Group::set_boolean`] for how existing key-value pairs are handled.
+    pub fn set_int64<'k: 'a>(&mut self, key: Key<'k>, value: i64) {
+        self.set(key, None, Value::from(value));
+    }
+
+    /// ### Method for setting the value associated with the given key to a 64-bit unsigned integer
+    ///
+    /// See [`Group::set_boolean`] for how existing key-value pairs are handled.
+    pub fn set_uint64<'k: 'a>(&mut self, key: Key<'k>, value: u64) {
+        self.set(key, None, Value::from(value));
+    }
+
+    /// ### Method for setting the value associated with the given key to a double-precision floating-point number
+    ///
+    /// See [`Group::set_boolean`] for how existing key-value pairs are handled.
+    pub fn set_double<'k: 'a>(&mut self, key: Key<'k>, value: f64) {
+        self.set(key, None, Value::from(value));
+    }
+
+    /// ### Method for getting the value associated with the given key as a list of strings
+    ///
+    /// List elements are separated by the list separator (`;` by default, see [`KeyFile::set_list_separator`]), and
+    /// separator characters that are part of a list element are escaped with a `\` character (i.e. `\;`). A trailing
+    /// separator is optional. Escape sequences in the list elements are decoded, and
+    /// elements that do not contain any escape sequences are borrowed from the raw value.
+    ///
+    /// If there is no key-value pair associated with the given key, then [`None`] is returned.
+    ///
+    /// ```
+    /// use keyfile::KeyFile;
+    ///
+    /// let keyfile = KeyFile::parse("[Desktop Entry]\nCategories=GNOME;GTK;Utility;\n").unwrap();
+    /// let group = keyfile.get_group("Desktop Entry").unwrap();
+    ///
+    /// let categories = group.get_string_list("Categories").unwrap().unwrap();
+    /// assert_eq!(categories, ["GNOME", "GTK", "Utility"]);
+    /// ```
+    pub fn get_string_list(&self, key: &str) -> Result<Option<Vec<Cow<'_, str>>>, InvalidValue> {
+        self.get_with(key, |value| split_list(value, self.list_separator.into()))
+    }
+
+    /// ### Method for setting the value associated with the given key to a list of strings
+    ///
+    /// List elements are escaped, separated by the list separator, and followed by a trailing separator. If a key-value
+    /// pair is already associated with the given key, only its value is replaced, and the surrounding whitespace and
+    /// decor are preserved. Otherwise, a new key-value pair is appended to the [`Group`].
+    ///
+    /// If any list element cannot be stored as a valid [`Value`] (i.e. it contains control characters other than
+    /// newlines, tabs, and carriage returns), an error is returned and the [`Group`] is not modified.
+    ///
+    /// ```
+    /// use keyfile::{types::Key, KeyFile};
+    ///
+    /// let mut keyfile = KeyFile::parse("[Desktop Entry]\n# Main categories\nCategories\t=\tGNOME;\n").unwrap();
+    /// let group = keyfile.get_group_mut("Desktop Entry").unwrap();
+    ///
+    /// group
+    ///     .set_string_list(Key::try_from("Categories").unwrap(), &["GNOME", "Audio;Video"])
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     group.to_string(),
+    ///     "[Desktop Entry]\n# Main categories\nCategories\t=\tGNOME;Audio\\;Video;\n"
+    /// );
+    /// ```
+    pub fn set_string_list<'k: 'a, S: AsRef<str>>(&mut self, key: Key<'k>, values: &[S]) -> Result<(), InvalidString> {
+        let value = Value::try_from(join_list(values, self.list_separator.into()))?;
+        self.set(key, None, value);
+        Ok(())
+    }
+
+    /// ### Method for getting the value associated with the given key as a list of booleans
+    ///
+    /// Every list element is parsed with the same rules as [`Group::get_boolean`]. If any element cannot be parsed,
+    /// the returned error contains the index of the first invalid element.
+    ///
+    /// If there is no key-value pair associated with the given key, then [`None`] is returned.
+    pub fn get_boolean_list(&self, key: &str) -> Result<Option<Vec<bool>>, InvalidValue> {
+        self.get_list_with(key, parse_as_boolean)
+    }
+
+    /// ### Method for getting the value associated with the given key as a list of 32-bit signed integers
+    ///
+    /// Every list element is parsed with the same rules as [`Group::get_integer`]. If any element cannot be parsed,
+    /// the returned error contains the index of the first invalid element.
+    ///
+    /// If there is no key-value pair associated with the given key, then [`None`] is returned.
+    ///
+    /// ```
+    /// use keyfile::{KeyFile, ValueError};
+    ///
+    /// let keyfile = KeyFile::parse("[Window]\nGeometry=0;0;800;600;\nColumns=120;x;80;\n").unwrap();
+    /// let group = keyfile.get_group("Window").unwrap();
+    ///
+    /// assert_eq!(group.get_integer_list("Geometry").unwrap(), Some(vec![0, 0, 800, 600]));
+    ///
+    /// let error = group.get_integer_list("Columns").unwrap_err();
+    /// assert!(matches!(error.source, ValueError::InvalidListElement { index: 1, .. }));
+    /// ```
+    pub fn get_integer_list(&self, key: &str) -> Result<Option<Vec<i32>>, InvalidValue> {
+        self.get_list_with(key, parse_as_integer)
+    }
+
+    /// ### Method for getting the value associated with the given key as a list of floating-point numbers
+    ///
+    /// Every list element is parsed with the same rules as [`Group::get_double`]. If any element cannot be parsed,
+    /// the returned error contains the index of the first invalid element.
+    ///
+    /// If there is no key-value pair associated with the given key, then [`None`] is returned.
+    pub fn get_double_list(&self, key: &str) -> Result<Option<Vec<f64>>, InvalidValue> {
+        self.get_list_with(key, parse_as_double)
+    }
+
+    /// ### Method for setting the value associated with the given key to a list of booleans
+    ///
+    /// See [`Group::set_string_list`] for how the list is written and how existing key-value pairs are handled.
+    pub fn set_boolean_list<'k: 'a>(&mut self, key: Key<'k>, values: &[bool]) {
+        self.set_list_with(key, values);
+    }
+
+    /// ### Method for setting the value associated with the given key to a list of 32-bit signed integers
+    ///
+    /// See [`Group::set_string_list`] for how the list is written and how existing key-value pairs are handled.
+    pub fn set_integer_list<'k: 'a>(&mut self, key: Key<'k>, values: &[i32]) {
+        self.set_list_with(key, values);
+    }
+
+    /// ### Method for setting the value associated with the given key to a list of floating-point numbers
+    ///
+    /// See [`Group::set_string_list`] for how the list is written and how existing key-value pairs are handled.
+    pub fn set_double_list<'k: 'a>(&mut self, key: Key<'k>, values: &[f64]) {
+        self.set_list_with(key, values);
+    }
+
+    fn get_with<'s, T, F>(&'s self, key: &str, f: F) -> Result<Option<T>, InvalidValue>
+    where
+        F: FnOnce(&'s str) -> Result<T, ValueError>,
+    {
+        let Some(kv) = self.get(key, None) else {
+            return Ok(None);
+        };
+
+        f(&kv.value)
+            .map(Some)
+            .map_err(|error| InvalidValue::new(&self.name, kv, error))
+    }
+
+    fn get_list_with<T>(
+        &self,
+        key: &str,
+        f: fn(&str) -> Result<T, ValueError>,
+    ) -> Result<Option<Vec<T>>, InvalidValue> {
+        self.get_with(key, |value| {
+            split_list(value, self.list_separator.into())?
+                .iter()
+                .enumerate()
+                .map(|(index, element)| f(element).map_err(|error| ValueError::invalid_list_element(index, error)))
+                .collect()
+        })
+    }
+
+    fn set_list_with<'k: 'a, T>(&mut self, key: Key<'k>, values: &[T])
+    where
+        T: Copy + Into<Value<'static>>,
+    {
+        let elements: Vec<Cow<str>> = values.iter().map(|value| (*value).into().into()).collect();
+
+        // joining valid values with the (printable) list separator always results in a valid value
+        let value = Value::new_unchecked(Cow::Owned(join_list(&elements, self.list_separator.into())));
+        self.set(key, None, value);
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn get_localized_with<'s, T, F>(
+        &'s self,
+        kv: Option<&'s KeyValuePair<'a>>,
+        f: F,
+    ) -> Result<Option<(T, Option<&'s Locale<'a>>)>, InvalidValue>
+    where
+        F: FnOnce(&'s str) -> Result<T, ValueError>,
+    {
+        let Some(kv) = kv else {
+            return Ok(None);
+        };
+
+        f(&kv.value)
+            .map(|value| Some((value, kv.locale.as_ref())))
+            .map_err(|error| InvalidValue::new(&self.name, kv, error))
+    }
+
+    fn find_translation(&self, key: &str, locale: &Locale) -> Option<&KeyValuePair<'a>> {
+        // fast path: the locale specifier of the translation does not have an encoding
+        self.get(key, Some(locale)).or_else(|| {
+            self.entries.values().find(|kv| {
+                kv.key == key
+                    && kv
+                        .locale
+                        .as_ref()
+                        .map_or(false, |l| l.encoding.is_some() && l.matches(locale))
+            })
+        })
+    }
+
+    /// Change the key and / or locale of an entry with the given function while keeping its position and checking for
+    /// collisions with other entries.
+    fn rekey(
+        &mut self,
+        key: &str,
+        locale: Option<&Locale>,
+        new_key: &str,
+        new_locale: Option<&Locale>,
+        change: impl FnOnce(&mut KeyValuePair<'a>),
+    ) -> Result<(), RekeyError> {
+        let query = EntryQuery { key, locale };
+        let Some(index) = self.entries.get_index_of(&query) else {
+            return Err(RekeyError::NotFound {
+                key: compound_key(key, locale),
+            });
+        };
+
+        let new_query = EntryQuery {
+            key: new_key,
+            locale: new_locale,
+        };
+        match self.entries.get_index_of(&new_query) {
+            Some(other) if other == index => return Ok(()),
+            Some(_) => {
+                return Err(RekeyError::Collision {
+                    key: compound_key(new_key, new_locale),
+                })
+            },
+            None => {},
+        }
+
+        let Some((_, mut kv)) = self.entries.shift_remove_index(index) else {
+            return Err(RekeyError::NotFound {
+                key: compound_key(key, locale),
+            });
+        };
+
+        change(&mut kv);
+        self.insert(kv);
+        self.entries.move_index(self.entries.len() - 1, index);
+
+        Ok(())
+    }
+
+    fn set<'k: 'a>(&mut self, key: Key<'k>, locale: Option<Locale<'k>>, value: Value<'k>) {
+        let key: Cow<str> = key.into();
+
+        if let Some(kv) = self.entries.get_mut(&EntryQuery {
+            key: &key,
+            locale: locale.as_ref(),
+        }) {
+            kv.set_value(value);
+        } else {
+            let position = self.translation_position(&key, locale.as_ref());
+
+            let mut kv = KeyValuePair::new(Key::new_unchecked(key), value);
+            kv.locale = locale;
+            self.insert(kv);
+
+            if let Some(position) = position {
+                self.entries.move_index(self.entries.len() - 1, position);
+            }
+        }
+    }
+
+    /// Position of a new translation, which is kept next to the other key-value pairs with the same key.
+    fn translation_position(&self, key: &str, locale: Option<&Locale>) -> Option<usize> {
+        locale?;
+        let last = self.entries.keys().rposition(|(other, _)| other == key)?;
+        Some(last + 1)
+    }
+
+    /// ### Method for inserting a new [`KeyValuePair`] into the [`Group`]
+    ///
+    /// The key-value pair will be appended as the last entry in the [`Group`].
+    ///
+    /// Inserting a key-value pair with the same key as an already existing key-value pair will
+    /// replace the existing key-value pair. In this case, the replaced value is returned.
+    pub fn insert<'kv: 'a>(&mut self, kv: KeyValuePair<'kv>) -> Option<KeyValuePair<'a>> {
+        // This clone is cheap only if the kv.key is a Cow::Borrowed(&str).
+        // If kv.key is a Cow::Owned(String), the String needs to be copied.
+        self.entries.insert((kv.key.clone(), kv.locale.clone()), kv)
+    }
+
+    /// ### Method for inserting a new [`KeyValuePair`] into the [`Group`] before the given key-value pair
+    ///
+    /// This method is equivalent to [`Group::insert`] except for the position of the inserted key-value pair. If a
+    /// key-value pair with the same key and locale already exists, it is replaced and moved to the new position. If
+    /// there is no key-value pair associated with the given key and locale, then an error is returned and the [`Group`]
+    /// is not modified.
+    ///
+    /// ```
+    /// use keyfile::{types::*, KeyFile, KeyValuePair};
+    ///
+    /// let mut keyfile = KeyFile::parse("[Desktop Entry]\nName=Files\nExec=nautilus\n").unwrap();
+    /// let group = keyfile.get_group_mut("Desktop Entry").unwrap();
+    ///
+    /// let kv = KeyValuePair::new(Key::try_from("Icon").unwrap(), Value::try_from("files").unwrap());
+    /// group.insert_after("Name", None, kv).unwrap();
+    /// assert_eq!(group.to_string(), "[Desktop Entry]\nName=Files\nIcon = files\nExec=nautilus\n");
+    ///
+    /// group.move_entry("Exec", None, 0).unwrap();
+    /// assert_eq!(group.to_string(), "[Desktop Entry]\nExec=nautilus\nName=Files\nIcon = files\n");
+    /// ```
+    pub fn insert_before<'kv: 'a>(
+        &mut self,
+        key: &str,
+        locale: Option<&Locale>,
+        kv: KeyValuePair<'kv>,
+    ) -> Result<Option<KeyValuePair<'a>>, PositionError> {
+        self.insert_next_to(key, locale, kv, false)
+    }
+
+    /// ### Method for inserting a new [`KeyValuePair`] into the [`Group`] after the given key-value pair
+    ///
+    /// See [`Group::insert_before`].
+    pub fn insert_after<'kv: 'a>(
+        &mut self,
+        key: &str,
+        locale: Option<&Locale>,
+        kv: KeyValuePair<'kv>,
+    ) -> Result<Option<KeyValuePair<'a>>, PositionError> {
+        self.insert_next_to(key, locale, kv, true)
+    }
+
+    fn insert_next_to<'kv: 'a>(
+        &mut self,
+        key: &str,
+        locale: Option<&Locale>,
+        kv: KeyValuePair<'kv>,
+        after: bool,
+    ) -> Result<Option<KeyValuePair<'a>>, PositionError> {
+        let anchor = EntryQuery { key, locale };
+        let kv: KeyValuePair<'a> = kv;
+
+        insert_next_to(
+            &mut self.entries,
+            &anchor,
+            (kv.key.clone(), kv.locale.clone()),
+            kv,
+            after,
+        )
+        .ok_or_else(|| PositionError::KeyNotFound {
+            key: compound_key(key, locale),
+        })
+    }
+
+    /// ### Method for moving the [`KeyValuePair`] associated with the given key and locale to the given position
+    ///
+    /// Key-value pairs between the old and the new position are shifted to make room.
+    ///
+    /// If there is no key-value pair associated with the given key and locale, or if the position is not less than the
+    /// number of key-value pairs, then an error is returned and the [`Group`] is not modified.
+    pub fn move_entry(&mut self, key: &str, locale: Option<&Locale>, index: usize) -> Result<(), PositionError> {
+        let from =
+            self.entries
+                .get_index_of(&EntryQuery { key, locale })
+                .ok_or_else(|| PositionError::KeyNotFound {
+                    key: compound_key(key, locale),
+                })?;
+        move_to(&mut self.entries, from, index)
+    }
+
+    /// ### Method for removing a [`KeyValuePair`] associated with the given key
+    ///
+    /// If there is no key-value pair associated with the given key, then [`None`] is returned.
+    ///
+    /// This operation preserves the order of the remaining key-value pairs.
+    pub fn remove(&mut self, key: &str, locale: Option<&Locale>) -> Option<KeyValuePair<'a>> {
+        self.entries.shift_remove(&EntryQuery { key, locale })
+    }
+
+    /// Method for getting the comments / empty lines preceding the group header
+    pub fn get_decor(&self) -> &[Cow<'a, str>] {
+        self.decor.lines()
+    }
+
+    /// ### Method for setting the comments / empty lines preceding the group header
+    ///
+    /// The replaced strings are returned. Use [`Group::take_decor`] for moving comments between items.
+    pub fn set_decor<'d: 'a>(&mut self, decor: Decor<'d>) -> Vec<Cow<'a, str>> {
+        std::mem::replace(&mut self.decor, decor).into()
+    }
+
+    /// ### Method for removing the comments / empty lines preceding the group header
+    ///
+    /// The removed lines are returned as [`Decor`], including lines that cannot be created with [`Decor::try_from`]
+    /// (like indented comment lines in [`ParseOptions::glib_compat`] mode, or invalid lines that are kept by
+    /// [`KeyFile::parse_lenient`]). This makes it possible to move comments between items:
+    ///
+    /// ```
+    /// use keyfile::{KeyFile, ParseOptions};
+    ///
+    /// let options = ParseOptions::new().glib_compat(true);
+    /// let mut keyfile = KeyFile::parse_with_options("[A]\nName=a\n  # end of A\n[B]\nName=b\n", &options).unwrap();
+    ///
+    /// let decor = keyfile.get_group_mut("B").unwrap().take_decor();
+    /// keyfile.get_group_mut("A").unwrap().set_trailing(decor);
+    ///
+    /// keyfile.remove_group("B");
+    /// assert_eq!(keyfile.to_string(), "[A]\nName=a\n  # end of A\n");
+    /// ```
+    pub fn take_decor(&mut self) -> Decor<'a> {
+        std::mem::take(&mut self.decor)
+    }
+
+    /// Method for getting the comments / empty lines after the last key-value pair of the group
+    pub fn get_trailing(&self) -> &[Cow<'a, str>] {
+        self.trailing.lines()
+    }
+
+    /// ### Method for setting the comments / empty lines after the last key-value pair of the group
+    ///
+    /// The replaced strings are returned.
+    pub fn set_trailing<'d: 'a>(&mut self, decor: Decor<'d>) -> Vec<Cow<'a, str>> {
+        std::mem::replace(&mut self.trailing, decor).into()
+    }
+
+    /// ### Method for removing the comments / empty lines after the last key-value pair of the group
+    ///
+    /// See [`Group::take_decor`].
+    pub fn take_trailing(&mut self) -> Decor<'a> {
+        std::mem::take(&mut self.trailing)
+    }
+
+    /// ### Method for getting the text of the comment preceding the group header
+    ///
+    /// See [`KeyFile::get_comment`] for how the comment text is derived from the comment lines.
+    pub fn get_comment(&self) -> Option<String> {
+        comment_text(&self.decor, false)
+    }
+
+    /// ### Method for setting the text of the comment preceding the group header
+    ///
+    /// See [`KeyFile::set_comment`] for how the comment text is written as comment lines, and which comment texts are
+    /// rejected as invalid.
+    pub fn set_comment(&mut self, comment: Option<&str>) -> Result<(), InvalidString> {
+        if let Some(span) = comment.and_then(invalid_comment_span) {
+            return Err(InvalidString::Comment { span });
+        }
+
+        replace_comment(&mut self.decor, comment, false);
+        Ok(())
+    }
+
+    /// Key-value pair for a key that includes the locale specifier (if any), like the keys of GLib.
+    fn get_compound(&self, key: &str) -> Result<&KeyValuePair<'a>, CommentError> {
+        split_compound_key(key)
+            .and_then(|(base, locale)| self.get(base, locale.as_ref()))
+            .ok_or_else(|| CommentError::KeyNotFound { key: String::from(key) })
+    }
+
+    /// Mutable version of [`Group::get_compound`].
+    fn get_compound_mut(&mut self, key: &str) -> Result<KeyValuePairMut<'_, 'a>, CommentError> {
+        match split_compound_key(key) {
+            Some((base, locale)) => self.get_mut(base, locale.as_ref()),
+            None => None,
+        }
+        .ok_or_else(|| CommentError::KeyNotFound { key: String::from(key) })
+    }
+
+    /// ### Method for getting the span of the group header in the source string (see [`Source`])
+    ///
+    /// The span includes the surrounding `[` and `]` characters.
+    pub fn header_span(&self, source: &Source) -> Option<Span> {
+        source.span_around(&self.name, 1, 1)
+    }
+
+    /// Method for getting the spans of the comments / empty lines preceding the group header in the source string (see
+    /// [`Source`])
+    pub fn decor_spans(&self, source: &Source) -> Vec<Option<Span>> {
+        self.decor.lines().iter().map(|line| source.span_of(line)).collect()
+    }
+}
+
+/// Key including the locale specifier (if any), as it is written in the file.
+pub(crate) fn compound_key(key: &str, locale: Option<&Locale>) -> String {
+    match locale {
+        Some(locale) => format!("{}[{}]", key, locale),
+        None => String::from(key),
+    }
+}
+
+/// Insert an item into the map and move it next to the anchor item (replacing and moving an item with the same key).
+///
+/// If there is no anchor item, then [`None`] is returned and the map is not modified.
+fn insert_next_to<K, V, Q>(map: &mut IndexMap<K, V>, anchor: &Q, key: K, value: V, after: bool) -> Option<Option<V>>
+where
+    K: Hash + Eq,
+    Q: ?Sized + Hash + Equivalent<K>,
+{
+    map.get_index_of(anchor)?;
+
+    let (index, previous) = map.insert_full(key, value);
+    let anchor = map.get_index_of(anchor)?;
+
+    // the anchor item shifts when the inserted item is moved from a position before it
+    let target = match (after, index < anchor) {
+        (false, true) => anchor - 1,
+        (true, false) if index != anchor => anchor + 1,
+        _ => anchor,
+    };
+    map.move_index(index, target);
+
+    Some(previous)
+}
+
+/// Move an item of the map to the given position, checking that the position is valid.
+fn move_to<K, V>(map: &mut IndexMap<K, V>, from: usize, index: usize) -> Result<(), PositionError> {
+    if index >= map.len() {
+        return Err(PositionError::OutOfBounds { index, len: map.len() });
+    }
+
+    map.move_index(from, index);
+    Ok(())
+}
+
+/// Split a key that includes a locale specifier (like `Name[de]`) into the key and the locale.
+///
+/// If the locale specifier is not valid, then [`None`] is returned.
+fn split_compound_key(key: &str) -> Option<(&str, Option<Locale<'_>>)> {
+    match key.strip_suffix(']').and_then(|rest| rest.split_once('[')) {
+        Some((base, locale)) => Some((base, Some(Locale::try_from(locale).ok()?))),
+        None => Some((key, None)),
+    }
+}
+
+/// Carriage return that is written at the end of a line if it is not part of the line ending.
+fn carriage_return(cr: bool) -> &'static str {
+    if cr {
+        "\r"
+    } else {
+        ""
+    }
+}
+
+/// Remove carriage returns that are preserved at the end of decor lines in files with mixed line endings.
+fn strip_carriage_returns(lines: &mut [Cow<str>]) {
+    for line in lines {
+        match line {
+            Cow::Borrowed(borrowed) => *borrowed = borrowed.strip_suffix('\r').unwrap_or(borrowed),
+            Cow::Owned(owned) => {
+                if owned.ends_with('\r') {
+                    owned.pop();
+                }
+            },
+        }
+    }
+}
+
+/// Whether a decor line is empty (or only contains whitespace).
+pub(crate) fn is_blank(line: &str) -> bool {
+    line.trim().is_empty()
+}
+
+/// Text of a comment line without its indentation and `#` character, or [`None`] for other decor lines.
+fn comment_line(line: &str) -> Option<&str> {
+    let text = line.trim_start().strip_prefix('#')?;
+    Some(text.strip_suffix('\r').unwrap_or(text))
+}
+
+/// Range of the comment lines in decor lines.
+///
+/// The comment at the top of the file ranges from the first to the last comment line, while the comment of an item
+/// only consists of the contiguous comment lines directly above it.
+fn comment_range(decor: &[Cow<str>], top: bool) -> Option<Range<usize>> {
+    let end = decor.iter().rposition(|line| comment_line(line).is_some())? + 1;
+    if !top && end != decor.len() {
+        return None;
+    }
+
+    let start = if top {
+        decor.iter().position(|line| comment_line(line).is_some())?
+    } else {
+        decor
+            .iter()
+            .rposition(|line| comment_line(line).is_none())
+            .map_or(0, |index| index + 1)
+    };
+    Some(start..end)
+}
+
+/// Comment text of decor lines, consisting of the comment lines (and the empty lines between them).
+fn comment_text(decor: &Decor, top: bool) -> Option<String> {
+    let decor = decor.comment_lines();
+    let lines: Vec<&str> = decor[comment_range(decor, top)?]
+        .iter()
+        .filter_map(|line| if is_blank(line) { Some("") } else { comment_line(line) })
+        .collect();
+    Some(lines.join("\n"))
+}
+
+/// Replace the comment lines (and the empty lines between them) in decor lines with the lines of the comment text,
+/// keeping all other lines in place.
+fn replace_comment(decor: &mut Decor, comment: Option<&str>, top: bool) {
+    let lines = decor.comment_lines();
+    let range = comment_range(lines, top).unwrap_or(lines.len()..lines.len());
+
+    let lines = comment
+        .filter(|comment| !comment.is_empty())
+        .into_iter()
+        .flat_map(|comment| comment.split('\n'))
+        .map(|line| Cow::Owned(format!("#{}", line.strip_suffix('\r').unwrap_or(line))));
+    decor.splice_comment_lines(range, lines);
+}
+
+/// Span of the first character in a comment text that cannot be written as part of a comment line, which are control
+/// characters except for tab and line breaks (`\n` or `\r\n`).
+fn invalid_comment_span(comment: &str) -> Option<Range<usize>> {
+    let mut chars = comment.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let valid = match c {
+            '\t' | '\n' => true,
+            '\r' => chars.peek().map_or(false, |(_, next)| *next == '\n'),
+            _ => !c.is_ascii_control(),
+        };
+        if !valid {
+            return Some(index..index + c.len_utf8());
+        }
+    }
+
+    None
+}
+
+/// Borrowed version of the `(key, locale)` tuples that are used as keys in [`Group::entries`], which allows looking up
+/// entries without constructing a `(Cow<str>, Option<Locale>)` tuple with a matching lifetime.
+#[derive(Debug)]
+struct EntryQuery<'q> {
+    key: &'q str,
+    locale: Option<&'q Locale<'q>>,
+}
+
+impl<'q> Hash for EntryQuery<'q> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // this needs to match the Hash implementation for (Cow<str>, Option<Locale>)
+        self.key.hash(state);
+        self.locale.hash(state);
+    }
+}
+
+impl<'q, 'a> Equivalent<(Cow<'a, str>, Option<Locale<'a>>)> for EntryQuery<'q> {
+    fn equivalent(&self, key: &(Cow<'a, str>, Option<Locale<'a>>)) -> bool {
+        self.key == key.0 && self.locale == key.1.as_ref()
+    }
+}
+
+impl<'a> Group<'a> {
+    pub(crate) fn write_lines(&self, w: &mut LineWriter) -> fmt::Result {
+        for line in self.decor.lines() {
+            w.line(format_args!("{}", line))?;
+        }
+        w.line(format_args!(
+            "{}[{}]{}{}",
+            self.indent,
+            self.name,
+            self.ws,
+            carriage_return(self.cr)
+        ))?;
+
+        for kv in self.entries.values() {
+            kv.write_lines(w)?;
+        }
+
+        for line in self.trailing.lines() {
+            w.line(format_args!("{}", line))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Display for Group<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut w = LineWriter::new(f, "\n");
+        self.write_lines(&mut w)?;
+        w.finish(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_ungrouped_key_value_pair() {
+        let error = KeyFile::parse("# comment\nName=Files\n[Desktop Entry]\n").unwrap_err();
+        assert!(matches!(
+            error,
+            KeyFileError::UngroupedKeyValuePair { lineno: 1, ref line, .. } if line == "Name=Files"
+        ));
+    }
+
+    #[test]
+    fn test_parse_lenient() {
+        let input = "Ungrouped=1\n[A]\nkey=value\n# comment\ninvalid\n\n[B]\nName[pt-BR]=x\nName=y\n";
+        let (keyfile, warnings) = KeyFile::parse_lenient(input, &ParseOptions::default()).unwrap();
+        assert_eq!(keyfile.to_string(), input);
+        assert_eq!(warnings.len(), 3);
+
+        let a = keyfile.get_group("A").unwrap();
+        assert_eq!(a.get_decor(), ["Ungrouped=1"]);
+        assert_eq!(a.get_trailing(), ["# comment", "invalid"]);
+
+        let b = keyfile.get_group("B").unwrap();
+        assert_eq!(b.get_decor(), [""]);
+        assert_eq!(b.get("Name", None).unwrap().get_decor(), ["Name[pt-BR]=x"]);
+
+        // duplicates are still errors
+        assert!(KeyFile::parse_lenient("[A]\n[A]\n", &ParseOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_parse_glib_compat() {
+        let input = "\t[A]\n  key = value\n \n\t# comment\n  other[de]=x\n";
+        assert!(matches!(
+            KeyFile::parse(input),
+            Err(KeyFileError::InvalidLine { lineno: 0, .. })
+        ));
+
+        let options = ParseOptions::new().glib_compat(true);
+        let mut keyfile = KeyFile::parse_with_options(input, &options).unwrap();
+        assert_eq!(keyfile.to_string(), input);
+
+        // indentation is preserved when values are modified
+        let group = keyfile.get_group_mut("A").unwrap();
+        group.set_string(Key::try_from("key").unwrap(), "new").unwrap();
+        assert_eq!(
+            group
+                .get("other", Some(&Locale::try_from("de").unwrap()))
+                .unwrap()
+                .get_decor(),
+            [" ", "\t# comment"]
+        );
+        assert_eq!(
+            keyfile.into_owned().to_string(),
+            "\t[A]\n  key = new\n \n\t# comment\n  other[de]=x\n"
+        );
+
+        // whitespace after group headers
+        let input = "[A]  \nk=v\n\t[B]\t \r\n";
+        assert!(matches!(
+            KeyFile::parse(input),
+            Err(KeyFileError::InvalidLine {
+                lineno: 0,
+                reason: InvalidLineReason::TrailingCharacters,
+                ..
+            })
+        ));
+        let keyfile = KeyFile::parse_with_options(input, &options).unwrap();
+        assert_eq!(keyfile.group_names().collect::<Vec<_>>(), ["A", "B"]);
+        assert_eq!(keyfile.get_group("A").unwrap().get_string("k").unwrap().unwrap(), "v");
+        assert_eq!(keyfile.to_string(), input);
+
+        let source = Source::new(input);
+        let span = keyfile.get_group("B").unwrap().header_span(&source).unwrap();
+        assert_eq!(&input[span.range()], "[B]");
+
+        assert!(KeyFile::parse_with_options("[A] x\n", &options).is_err());
+        assert!(matches!(
+            KeyFile::parse_with_options("[A]\n[A] \n", &options),
+            Err(KeyFileError::DuplicateGroup { span, .. }) if span.range() == (4..7)
+        ));
+    }
+
+    #[test]
+    fn test_parse_duplicate_policy() {
+        let input = "[A]\nk=1\nx=0\n# c\nk=2\n[B]\nb=1\n[A]\nk=3\n";
+        assert!(matches!(
+            KeyFile::parse(input),
+            Err(KeyFileError::DuplicateKey { lineno: 4, .. })
+        ));
+
+        let parse = |policy| {
+            let options = ParseOptions::new().duplicate_policy(policy);
+            KeyFile::parse_lenient(input, &options).unwrap()
+        };
+        let names = |keyfile: &KeyFile| keyfile.groups.keys().map(ToString::to_string).collect::<Vec<_>>();
+
+        let (keyfile, warnings) = parse(DuplicatePolicy::FirstWins);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(names(&keyfile), ["A", "B"]);
+        assert_eq!(keyfile.get_group("A").unwrap().get_integer("k").unwrap(), Some(1));
+        assert_eq!(keyfile.to_string(), input);
+
+        let (keyfile, warnings) = parse(DuplicatePolicy::LastWins);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(names(&keyfile), ["B", "A"]);
+        assert_eq!(keyfile.get_group("A").unwrap().get_integer("k").unwrap(), Some(3));
+        assert_eq!(keyfile.to_string(), input);
+
+        let (keyfile, warnings) = parse(DuplicatePolicy::Merge);
+        assert_eq!(warnings.len(), 3);
+        assert_eq!(names(&keyfile), ["A", "B"]);
+        let group = keyfile.get_group("A").unwrap();
+        assert_eq!(group.get_integer("k").unwrap(), Some(3));
+        assert_eq!(group.get_integer("x").unwrap(), Some(0));
+        assert_eq!(keyfile.to_string(), "[A]\nk=1\nx=0\n# c\nk=2\nk=3\n[B]\nb=1\n");
+    }
+
+    #[test]
+    fn test_parse_shadowed_group() {
+        let input = "[A]\n[B]\n[A]\nbad\nx=1\nx=2\n";
+        let (keyfile, errors) = KeyFile::parse_with_errors(input, &ParseOptions::default());
+        assert!(matches!(
+            errors.as_slice(),
+            [
+                KeyFileError::DuplicateGroup { lineno: 2, .. },
+                KeyFileError::InvalidLine { lineno: 3, .. },
+                KeyFileError::DuplicateKey { lineno: 5, ref key, .. },
+            ] if key == "x"
+        ));
+        assert!(keyfile.get_group("A").unwrap().get("x", None).is_none());
+        assert_eq!(keyfile.to_string(), input);
+
+        // invalid lines are errors in strict mode even if the group is shadowed
+        let options = ParseOptions::new().duplicate_policy(DuplicatePolicy::FirstWins);
+        assert!(matches!(
+            KeyFile::parse_with_options(input, &options),
+            Err(KeyFileError::InvalidLine { lineno: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_get_locale_string() {
+        let input = "[A]\nName=Files\nName[de]=Dateien\nName[de_DE@euro]=Euro\nName[sr@latin]=Latinica\n\
+                     Name[fr_FR.ISO-8859-15]=Fichiers\nComment=a\\tb\nComment[de]=c\\td\n";
+        let keyfile = KeyFile::parse(input).unwrap();
+        let group = keyfile.get_group("A").unwrap();
+
+        let lookup = |key: &str, locale: &str| {
+            let locale = Locale::try_from(locale).unwrap();
+            let (value, matched) = group.get_locale_string(key, &locale).unwrap().unwrap();
+            (value.into_owned(), matched.map(ToString::to_string))
+        };
+        let matched = |value: &str, locale: Option<&str>| (String::from(value), locale.map(String::from));
+
+        // lang_COUNTRY@MODIFIER, lang_COUNTRY, lang@MODIFIER, lang, untranslated
+        assert_eq!(lookup("Name", "de_DE@euro"), matched("Euro", Some("de_DE@euro")));
+        assert_eq!(lookup("Name", "de_DE"), matched("Dateien", Some("de")));
+        assert_eq!(lookup("Name", "de_AT@euro"), matched("Dateien", Some("de")));
+        assert_eq!(lookup("Name", "sr_RS@latin"), matched("Latinica", Some("sr@latin")));
+        assert_eq!(lookup("Name", "sr_RS"), matched("Files", None));
+        assert_eq!(lookup("Name", "es"), matched("Files", None));
+
+        // the encoding of translations is ignored for matching, but kept in the matched locale
+        assert_eq!(lookup("Name", "fr_FR"), matched("Fichiers", Some("fr_FR")));
+        let (_, locale) = group
+            .get_locale_string("Name", &Locale::try_from("fr_FR").unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(locale.unwrap().get_encoding(), Some("ISO-8859-15"));
+        assert_eq!(lookup("Name", "fr"), matched("Files", None));
+
+        // escape sequences are decoded
+        assert_eq!(lookup("Comment", "de_CH"), matched("c\td", Some("de")));
+        assert_eq!(lookup("Comment", "it"), matched("a\tb", None));
+        assert!(group
+            .get_locale_string("Icon", &Locale::try_from("de").unwrap())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_get_best_locale_string() {
+        let input = "[A]\nName=Files\nName[de]=Dateien\nName[sr@latin]=Latinica\nName[en_GB]=Folders\nIcon=files\n";
+        let keyfile = KeyFile::parse(input).unwrap();
+        let group = keyfile.get_group("A").unwrap();
+
+        let lookup = |key: &str, preferences: &[&str]| {
+            let preferences: Vec<Locale> = preferences.iter().map(|l| Locale::try_from(*l).unwrap()).collect();
+            let (value, matched) = group.get_best_locale_string(key, &preferences).unwrap().unwrap();
+            (value.into_owned(), matched.map(ToString::to_string))
+        };
+        let matched = |value: &str, locale: Option<&str>| (String::from(value), locale.map(String::from));
+
+        assert_eq!(
+            lookup("Name", &["sr_RS@latin", "de"]),
+            matched("Latinica", Some("sr@latin"))
+        );
+        assert_eq!(
+            lookup("Name", &["it", "de_AT", "sr@latin"]),
+            matched("Dateien", Some("de"))
+        );
+
+        // a translation for a later preference wins over the untranslated value
+        assert_eq!(lookup("Name", &["en_US", "en_GB"]), matched("Folders", Some("en_GB")));
+        assert_eq!(lookup("Name", &["it", "es"]), matched("Files", None));
+        assert_eq!(lookup("Name", &[]), matched("Files", None));
+        assert_eq!(lookup("Icon", &["de"]), matched("files", None));
+
+        // preference lists from the environment
+        let vars = HashMap::from([(String::from("LANGUAGE"), String::from("sr@latin:de:en"))]);
+        let preferences = Locale::from_env_vars(&vars);
+        let kv = group.get_best_localized("Name", &preferences).unwrap();
+        assert_eq!(kv.get_value(), "Latinica");
+        let vars = HashMap::from([(String::from("LANGUAGE"), String::from("pt_BR:en_GB"))]);
+        let preferences = Locale::from_env_vars(&vars);
+        let kv = group.get_best_localized("Name", &preferences).unwrap();
+        assert_eq!(kv.get_value(), "Folders");
+    }
+
+    #[test]
+    fn test_check_base_keys() {
+        // base keys must be in the same group, and may follow their translations
+        let input = "[A]\nName[de]=x\nName=y\n[B]\nName=z\nComment[de]=a\n[C]\nComment[fr]=b\nComment[de]=c\n";
+        let options = ParseOptions::new().check_base_keys(true);
+
+        assert!(KeyFile::parse(input).is_ok());
+        let error = KeyFile::parse_with_options(input, &options).unwrap_err();
+        assert!(matches!(
+            error,
+            KeyFileError::MissingBaseKey { ref key, lineno: 5, .. } if key == "Comment[de]"
+        ));
+
+        // in lenient mode, every violation is reported as a warning and the keyfile is still returned
+        let (keyfile, warnings) = KeyFile::parse_lenient(input, &options).unwrap();
+        assert_eq!(keyfile.to_string(), input);
+        let reported: Vec<(String, usize)> = warnings
+            .iter()
+            .map(|warning| match warning {
+                KeyFileError::MissingBaseKey { key, lineno, .. } => (key.clone(), *lineno),
+                other => (other.to_string(), usize::MAX),
+            })
+            .collect();
+        assert_eq!(
+            reported,
+            [
+                (String::from("Comment[de]"), 5),
+                (String::from("Comment[fr]"), 7),
+                (String::from("Comment[de]"), 8)
+            ]
+        );
+
+        let (_, warnings) = KeyFile::parse_lenient(input, &ParseOptions::default()).unwrap();
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let roundtrip = |input: &str| KeyFile::parse(input).unwrap().to_string();
+
+        // CRLF without a final newline
+        let input = "# c\r\n[A]\r\nk=v\r\n\r\n[B]\r\nk=w";
+        let keyfile = KeyFile::parse(input).unwrap();
+        assert_eq!(keyfile.get_line_ending(), LineEnding::CrLf);
+        assert!(!keyfile.get_final_newline());
+        assert_eq!(keyfile.to_string().as_bytes(), input.as_bytes());
+
+        // LF without a final newline, also after trailing decor
+        for input in ["[A]\nk=v", "[A]\nk=v\n# end", "[A]", ""] {
+            assert_eq!(roundtrip(input).as_bytes(), input.as_bytes());
+        }
+        for input in ["[A]\r\nk=v\r\n", "[A]\nk=v\n\n", "\n"] {
+            assert_eq!(roundtrip(input).as_bytes(), input.as_bytes());
+        }
+
+        // mixed line endings are preserved for every line
+        let mixed = [
+            "[A]\nk=v\r\nx=y\n",
+            "# c\r\n\n[A]\r\nk=v\n\r\n# end\r\n",
+            "[A]\r\nk=v\r\nx=y",
+            "[A]\r\nk=v\r",
+            "\r\n\r",
+        ];
+        for input in mixed {
+            assert_eq!(roundtrip(input).as_bytes(), input.as_bytes());
+        }
+
+        let mut keyfile = KeyFile::parse(mixed[1]).unwrap();
+        assert_eq!(keyfile.get_line_ending(), LineEnding::Lf);
+        assert_eq!(keyfile.get_comment(None, None).unwrap().as_deref(), Some(" c"));
+        let group = keyfile.get_group_mut("A").unwrap();
+        assert_eq!(group.get_string("k").unwrap().as_deref(), Some("v"));
+        group.set_string(Key::try_from("k").unwrap(), "w").unwrap();
+        assert_eq!(keyfile.to_string(), "# c\r\n\n[A]\r\nk=w\n\r\n# end\r\n");
+
+        keyfile.set_line_ending(LineEnding::CrLf);
+        assert_eq!(keyfile.to_string(), "# c\r\n\r\n[A]\r\nk=w\r\n\r\n# end\r\n");
+        keyfile.set_line_ending(LineEnding::Lf);
+        assert_eq!(keyfile.to_string(), "# c\n\n[A]\nk=w\n\n# end\n");
+
+        // normalizing
+        let mut keyfile = KeyFile::parse(input).unwrap();
+        assert_eq!(keyfile.set_line_ending(LineEnding::Lf), LineEnding::CrLf);
+        assert!(!keyfile.set_final_newline(true));
+        assert_eq!(keyfile.to_string().as_bytes(), b"# c\n[A]\nk=v\n\n[B]\nk=w\n");
+
+        let mut keyfile = KeyFile::parse("[A]\nk=v\n").unwrap();
+        keyfile.set_line_ending(LineEnding::CrLf);
+        keyfile.set_final_newline(false);
+        assert_eq!(keyfile.to_string().as_bytes(), b"[A]\r\nk=v");
+    }
+
+    #[test]
+    fn test_spans() {
+        let input = "# top\n[A]\nName[de_DE.UTF-8@euro] = Dateien\n\n# end\n";
+        let keyfile = KeyFile::parse(input).unwrap();
+        let source = Source::new(input);
+        let text = |span: Option<Span>| &input[span.unwrap().range()];
+
+        let group = keyfile.get_group("A").unwrap();
+        assert_eq!(text(group.header_span(&source)), "[A]");
+        assert_eq!(text(group.decor_spans(&source)[0]), "# top");
+
+        let kv = group.entries.values().next().unwrap();
+        assert_eq!(text(kv.key_span(&source)), "Name");
+        assert_eq!(text(kv.locale_span(&source)), "de_DE.UTF-8@euro");
+        assert_eq!(text(kv.value_span(&source)), "Dateien");
+        assert_eq!(text(kv.span(&source)), "Name[de_DE.UTF-8@euro] = Dateien");
+        assert_eq!(kv.span(&source).unwrap().start.line, 2);
+
+        let spans = keyfile.decor_spans(&source);
+        assert_eq!(spans[1].unwrap().start.line, 4);
+
+        // modified or copied parts are not part of the source
+        let owned = keyfile.clone().into_owned();
+        assert!(owned.get_group("A").unwrap().header_span(&source).is_none());
+    }
+
+    #[test]
+    fn test_error_spans() {
+        let input = "[A]\r\nName=x\r\n  Name[de] = y\r\nName[de]=z\r\n";
+        let options = ParseOptions::new().glib_compat(true);
+        let error = KeyFile::parse_with_options(input, &options).unwrap_err();
+
+        assert!(matches!(error, KeyFileError::DuplicateKey { lineno: 3, .. }));
+        assert_eq!(&input[error.span().range()], "Name[de]");
+        assert_eq!(
+            error.render(input),
+            format!("{error}\n  |\n3 | Name[de]=z\n  | ^^^^^^^^\n")
+        );
+
+        let error = Locale::try_from("pt-BR").unwrap_err();
+        assert!(matches!(error, InvalidString::Locale { span } if span == (2..3)));
+        assert!(matches!(
+            Locale::try_from("de_DE.UTF-8"),
+            Err(InvalidString::Encoding { span }) if span == (5..11)
+        ));
+    }
+
+    #[test]
+    fn test_rekey() {
+        let input = "[A]\nName=Files\nName[de]=Dateien\nIcon=files\n";
+        let mut group = KeyFile::parse(input).unwrap().remove_group("A").unwrap();
+        let de = Locale::try_from("de").unwrap();
+        let fr = Locale::try_from("fr").unwrap();
+
+        group.change_locale("Name", Some(&de), Some(fr.clone())).unwrap();
+        assert!(group.get("Name", Some(&de)).is_none());
+        assert_eq!(group.get("Name", Some(&fr)).unwrap().get_value(), "Dateien");
+        assert_eq!(group.to_string(), "[A]\nName=Files\nName[fr]=Dateien\nIcon=files\n");
+
+        // unchanged keys are not collisions
+        group.rename_key("Icon", None, Key::try_from("Icon").unwrap()).unwrap();
+
+        assert!(matches!(
+            group.change_locale("Name", Some(&fr), None),
+            Err(RekeyError::Collision { ref key }) if key == "Name"
+        ));
+        assert!(matches!(
+            group.rename_key("Name", Some(&de), Key::try_from("Other").unwrap()),
+            Err(RekeyError::NotFound { ref key }) if key == "Name[de]"
+        ));
+        assert_eq!(group.entries.len(), 3);
+    }
+
+    #[test]
+    fn test_entry() {
+        let input = "[A]\nName=Files\nName[de]=Dateien\n";
+        let mut keyfile = KeyFile::parse_with_options(
+            input,
+            &ParseOptions::new().list_separator(ListSeparator::try_from(',').unwrap()),
+        )
+        .unwrap();
+        let de = Locale::try_from("de").unwrap();
+
+        // occupied group, occupied and vacant key-value pairs
+        let group = keyfile.entry(GroupName::try_from("A").unwrap()).or_insert();
+        let old = match group.entry(Key::try_from("Name").unwrap(), Some(de)) {
+            Entry::Occupied(mut entry) => Some(entry.insert(Value::try_from("Ordner").unwrap())),
+            Entry::Vacant(_) => None,
+        };
+        assert_eq!(old.as_deref(), Some("Dateien"));
+        let kv = group
+            .entry(Key::try_from("Name").unwrap(), Some(Locale::try_from("fr").unwrap()))
+            .or_insert_with(|| Value::try_from("Fichiers").unwrap());
+        assert_eq!(kv.get_locale().map(ToString::to_string).as_deref(), Some("fr"));
+
+        // vacant group is adapted to the KeyFile
+        let other = Group::new(GroupName::try_from("Other").unwrap());
+        let group = keyfile
+            .entry(GroupName::try_from("B").unwrap())
+            .or_insert_with(|| other);
+        assert_eq!(group.name, "B");
+        group
+            .set_string_list(Key::try_from("List").unwrap(), &["a", "b"])
+            .unwrap();
+
+        assert!(matches!(
+            keyfile.entry(GroupName::try_from("B").unwrap()),
+            GroupEntry::Occupied(ref entry) if entry.get().name == "B"
+        ));
+        assert_eq!(
+            keyfile.to_string(),
+            "[A]\nName=Files\nName[de]=Ordner\nName[fr] = Fichiers\n[B]\nList = a,b,\n"
+        );
+
+        // new translations are inserted next to their key, like with Group::set_locale_string
+        let mut keyfile = KeyFile::parse("[A]\nName=Files\nIcon=files\n").unwrap();
+        let group = keyfile.get_group_mut("A").unwrap();
+        group
+            .entry(Key::try_from("Name").unwrap(), Some(Locale::try_from("de").unwrap()))
+            .or_insert(Value::try_from("Dateien").unwrap());
+        group
+            .entry(Key::try_from("Comment").unwrap(), Some(Locale::try_from("de").unwrap()))
+            .or_insert(Value::try_from("Dateien verwalten").unwrap());
+        let mut other = group.clone();
+        other
+            .set_locale_string(
+                Key::try_from("Icon").unwrap(),
+                Locale::try_from("de").unwrap(),
+                "ordner",
+            )
+            .unwrap();
+        group
+            .entry(Key::try_from("Icon").unwrap(), Some(Locale::try_from("de").unwrap()))
+            .or_insert(Value::try_from("ordner").unwrap());
+        assert_eq!(group.to_string(), other.to_string());
+        assert_eq!(
+            group.to_string(),
+            "[A]\nName=Files\nName[de] = Dateien\nIcon=files\nIcon[de] = ordner\nComment[de] = Dateien verwalten\n"
+        );
+    }
+
+    #[test]
+    fn test_comments() {
+        let input = "# top\n\n# one\n\n# two\n[A]\nName=x\n\n#de\nName[de]=y\n\n[B]\n";
+        let mut keyfile = KeyFile::parse(input).unwrap();
+        assert_eq!(keyfile.header.lines(), ["# top", "", "# one"]);
+        assert_eq!(
+            keyfile.get_comment(None, None).unwrap().as_deref(),
+            Some(" top\n\n one")
+        );
+        assert_eq!(keyfile.get_comment(Some("A"), None).unwrap().as_deref(), Some(" two"));
+        assert_eq!(
+            keyfile.get_comment(Some("A"), Some("Name[de]")).unwrap().as_deref(),
+            Some("de")
+        );
+        assert_eq!(keyfile.get_comment(Some("A"), Some("Name")).unwrap(), None);
+        assert_eq!(keyfile.get_comment(Some("B"), None).unwrap(), None);
+
+        assert!(matches!(
+            keyfile.get_comment(Some("C"), None),
+            Err(CommentError::GroupNotFound { ref name }) if name == "C"
+        ));
+        assert!(matches!(
+            keyfile.set_comment(Some("A"), Some("Name[pt-BR]"), None),
+            Err(CommentError::KeyNotFound { ref key }) if key == "Name[pt-BR]"
+        ));
+
+        // separating empty lines are preserved
+        keyfile
+            .set_comment(Some("A"), Some("Name[de]"), Some("German"))
+            .unwrap();
+        keyfile.set_comment(Some("B"), None, Some("")).unwrap();
+        keyfile.set_comment(None, None, None).unwrap();
+        assert_eq!(
+            keyfile.to_string(),
+            "\n# two\n[A]\nName=x\n\n#German\nName[de]=y\n\n[B]\n"
+        );
+
+        // files without groups only have a comment at the top
+        let keyfile = KeyFile::parse("# only\n").unwrap();
+        assert_eq!(keyfile.get_comment(None, None).unwrap().as_deref(), Some(" only"));
+        assert!(keyfile.decor.lines().is_empty());
+
+        // a new comment at the top is separated from the comment of the first group
+        let mut keyfile = KeyFile::parse("# group\n[A]\n").unwrap();
+        keyfile.set_comment(None, None, Some(" top")).unwrap();
+        let string = keyfile.to_string();
+        assert_eq!(string, "# top\n\n# group\n[A]\n");
+
+        let keyfile = KeyFile::parse(&string).unwrap();
+        assert_eq!(keyfile.get_comment(None, None).unwrap().as_deref(), Some(" top"));
+        assert_eq!(keyfile.get_comment(Some("A"), None).unwrap().as_deref(), Some(" group"));
+
+        // groups that are inserted after setting the comment at the top are separated from it as well
+        let mut keyfile = KeyFile::new();
+        keyfile.set_comment(None, None, Some("x")).unwrap();
+        keyfile.insert_group(Group::new(GroupName::try_from("A").unwrap()));
+        let string = keyfile.to_string();
+        assert_eq!(string, "#x\n\n[A]\n");
+
+        let keyfile = KeyFile::parse(&string).unwrap();
+        assert_eq!(keyfile.get_comment(None, None).unwrap().as_deref(), Some("x"));
+        assert_eq!(keyfile.get_comment(Some("A"), None).unwrap(), None);
+    }
+
+    #[test]
+    fn test_comments_preserved_lines() {
+        // invalid lines that are preserved by lenient parsing are not comments
+        let input = "[A]\nName[pt-BR]=x\nName=y\n";
+        let (mut keyfile, _) = KeyFile::parse_lenient(input, &ParseOptions::default()).unwrap();
+        assert_eq!(keyfile.get_comment(Some("A"), Some("Name")).unwrap(), None);
+
+        keyfile.set_comment(Some("A"), Some("Name"), Some("note")).unwrap();
+        assert_eq!(keyfile.to_string(), "[A]\nName[pt-BR]=x\n#note\nName=y\n");
+        assert_eq!(
+            keyfile.get_comment(Some("A"), Some("Name")).unwrap().as_deref(),
+            Some("note")
+        );
+        keyfile.set_comment(Some("A"), Some("Name"), None).unwrap();
+        assert_eq!(keyfile.to_string(), input);
+
+        // comment lines before a preserved line are not part of the comment of the next item
+        let input = "[A]\n# a\ninvalid\n# b\nk=v\n";
+        let (mut keyfile, _) = KeyFile::parse_lenient(input, &ParseOptions::default()).unwrap();
+        assert_eq!(
+            keyfile.get_comment(Some("A"), Some("k")).unwrap().as_deref(),
+            Some(" b")
+        );
+        keyfile.set_comment(Some("A"), Some("k"), None).unwrap();
+        assert_eq!(keyfile.to_string(), "[A]\n# a\ninvalid\nk=v\n");
+        keyfile.set_comment(Some("A"), Some("k"), Some("c")).unwrap();
+        assert_eq!(keyfile.to_string(), "[A]\n# a\ninvalid\n#c\nk=v\n");
+
+        // neither are shadowed lines
+        let input = "[A]\n# old\nk=1\n# new\nk=2\n";
+        let options = ParseOptions::new().duplicate_policy(DuplicatePolicy::LastWins);
+        let mut keyfile = KeyFile::parse_with_options(input, &options).unwrap();
+        assert_eq!(
+            keyfile.get_comment(Some("A"), Some("k")).unwrap().as_deref(),
+            Some(" new")
+        );
+
+        keyfile.set_comment(Some("A"), Some("k"), Some(" k")).unwrap();
+        assert_eq!(keyfile.to_string(), "[A]\n# old\nk=1\n# k\nk=2\n");
+        keyfile.set_comment(Some("A"), Some("k"), None).unwrap();
+        assert_eq!(keyfile.to_string(), "[A]\n# old\nk=1\nk=2\n");
+        assert_eq!(keyfile.get_comment(Some("A"), Some("k")).unwrap(), None);
+
+        let input = "[A]\n# old\nk=1\nk=2\n";
+        let mut keyfile = KeyFile::parse_with_options(input, &options).unwrap();
+        assert_eq!(keyfile.get_comment(Some("A"), Some("k")).unwrap(), None);
+        keyfile.set_comment(Some("A"), Some("k"), Some("new")).unwrap();
+        assert_eq!(keyfile.to_string(), "[A]\n# old\nk=1\n#new\nk=2\n");
+
+        // the lines of a shadowed group are not the comment of the group that replaces it
+        let input = "# one\n[A]\na=1\n[A]\nb=2\n";
+        let mut keyfile = KeyFile::parse_with_options(input, &options).unwrap();
+        assert_eq!(keyfile.get_comment(Some("A"), None).unwrap(), None);
+        keyfile.set_comment(Some("A"), None, Some("two")).unwrap();
+        assert_eq!(keyfile.to_string(), "# one\n[A]\na=1\n#two\n[A]\nb=2\n");
+        assert_eq!(keyfile.get_comment(Some("A"), None).unwrap().as_deref(), Some("two"));
+        keyfile.set_comment(Some("A"), None, None).unwrap();
+        assert_eq!(keyfile.to_string(), "# one\n[A]\na=1\n[A]\nb=2\n");
+
+        let input = "[A]\na=1\n# two\n[A]\nb=2\n";
+        let keyfile = KeyFile::parse_with_options(input, &options).unwrap();
+        assert_eq!(keyfile.get_comment(Some("A"), None).unwrap().as_deref(), Some(" two"));
+
+        let options = ParseOptions::new().duplicate_policy(DuplicatePolicy::FirstWins);
+        let input = "[A]\na=1\n[A]\n# c\nb=2\n[B]\n";
+        let mut keyfile = KeyFile::parse_with_options(input, &options).unwrap();
+        assert_eq!(keyfile.get_comment(Some("B"), None).unwrap(), None);
+        keyfile.set_comment(Some("B"), None, Some("x")).unwrap();
+        assert_eq!(keyfile.to_string(), "[A]\na=1\n[A]\n# c\nb=2\n#x\n[B]\n");
+
+        // only the comment lines directly above an item are its comment
+        let input = "[A]\n# a\n\n# b\nk=v\n";
+        let mut keyfile = KeyFile::parse(input).unwrap();
+        assert_eq!(
+            keyfile.get_comment(Some("A"), Some("k")).unwrap().as_deref(),
+            Some(" b")
+        );
+        keyfile.set_comment(Some("A"), Some("k"), None).unwrap();
+        assert_eq!(keyfile.to_string(), "[A]\n# a\n\nk=v\n");
+        assert_eq!(keyfile.get_comment(Some("A"), Some("k")).unwrap(), None);
+        keyfile.set_comment(Some("A"), Some("k"), Some("c")).unwrap();
+        assert_eq!(keyfile.to_string(), "[A]\n# a\n\n#c\nk=v\n");
+
+        let mut keyfile = KeyFile::parse("# a\n[A]\n# b\n\n[B]\n").unwrap();
+        assert_eq!(keyfile.get_comment(Some("B"), None).unwrap(), None);
+        keyfile.set_comment(Some("B"), None, Some("c")).unwrap();
+        assert_eq!(keyfile.to_string(), "# a\n[A]\n# b\n\n#c\n[B]\n");
+
+        // comment texts with line breaks other than "\n" and "\r\n", or with control characters, are rejected
+        let mut keyfile = KeyFile::parse("[A]\nk=v\n").unwrap();
+        keyfile.set_comment(Some("A"), Some("k"), Some(" one\r\n two")).unwrap();
+        assert_eq!(keyfile.to_string(), "[A]\n# one\n# two\nk=v\n");
+        assert!(matches!(
+            keyfile.set_comment(Some("A"), Some("k"), Some("one\rtwo")),
+            Err(CommentError::InvalidComment { span }) if span == (3..4)
+        ));
+        assert!(matches!(
+            keyfile.set_comment(None, None, Some("\tbell\x07")),
+            Err(CommentError::InvalidComment { span }) if span == (5..6)
+        ));
+        let group = keyfile.get_group_mut("A").unwrap();
+        assert!(matches!(
+            group.set_comment(Some("a\r")),
+            Err(InvalidString::Comment { span }) if span == (1..2)
+        ));
+        assert!(matches!(
+            group.get_mut("k", None).unwrap().set_comment(Some("\x1b[1m")),
+            Err(InvalidString::Comment { span }) if span == (0..1)
+        ));
+        assert_eq!(keyfile.to_string(), "[A]\n# one\n# two\nk=v\n");
+
+        // indented comment lines
+        let options = ParseOptions::new().glib_compat(true);
+        let mut keyfile = KeyFile::parse_with_options("[A]\n  # hi\n\t#\tthere\nk=v\n", &options).unwrap();
+        assert_eq!(
+            keyfile.get_comment(Some("A"), Some("k")).unwrap().as_deref(),
+            Some(" hi\n\tthere")
+        );
+        keyfile.set_comment(Some("A"), Some("k"), Some("bye")).unwrap();
+        assert_eq!(keyfile.to_string(), "[A]\n#bye\nk=v\n");
+    }
+
+    #[test]
+    fn test_trailing_decor() {
+        let input = "# top\n\n[A]\n# key\nk=v\n# a\n[B]\nk=v\n\n# end\n";
+        let options = ParseOptions::new().trailing_decor(TrailingDecor::PreviousGroup);
+        let mut keyfile = KeyFile::parse_with_options(input, &options).unwrap();
+        assert_eq!(keyfile.to_string(), input);
+
+        let a = keyfile.get_group("A").unwrap();
+        assert_eq!(a.get_decor(), [""]);
+        assert_eq!(a.get("k", None).unwrap().get_decor(), ["# key"]);
+        assert_eq!(a.get_trailing(), ["# a"]);
+        assert_eq!(keyfile.get_group("B").unwrap().get_trailing(), ["", "# end"]);
+        assert!(keyfile.get_decor().is_empty());
+
+        keyfile.remove_group("B");
+        let previous = keyfile.set_decor(Decor::try_from(vec!["# file"]).unwrap());
+        assert!(previous.is_empty());
+        assert_eq!(keyfile.to_string(), "# top\n\n[A]\n# key\nk=v\n# a\n# file\n");
+    }
+
+    #[test]
+    fn test_take_decor() {
+        // indented comment lines are moved between items
+        let options = ParseOptions::new().glib_compat(true);
+        let input = "[A]\n  # x\n\t# y\nk=v\n [B] \n";
+        let mut keyfile = KeyFile::parse_with_options(input, &options).unwrap();
+
+        let a = keyfile.get_group_mut("A").unwrap();
+        let decor = a.get_mut("k", None).unwrap().take_decor();
+        assert!(a.get("k", None).unwrap().get_decor().is_empty());
+        a.set_trailing(decor);
+        assert_eq!(keyfile.to_string(), "[A]\nk=v\n  # x\n\t# y\n [B] \n");
+
+        let decor = keyfile.get_group_mut("A").unwrap().take_trailing();
+        let b = keyfile.get_group_mut("B").unwrap();
+        b.set_decor(decor);
+        assert_eq!(b.get_comment().as_deref(), Some(" x\n y"));
+        assert_eq!(keyfile.to_string(), "[A]\nk=v\n  # x\n\t# y\n [B] \n");
+
+        // preserved lines are moved as well, and they are still not treated as comment lines
+        let input = "[A]\nk=v\n# x\ninvalid\n[B]\n";
+        let (mut keyfile, _) = KeyFile::parse_lenient(input, &ParseOptions::default()).unwrap();
+        let decor = keyfile.get_group_mut("A").unwrap().take_trailing();
+        let b = keyfile.get_group_mut("B").unwrap();
+        b.set_decor(decor);
+        assert_eq!(b.get_comment(), None);
+        b.set_comment(Some("b")).unwrap();
+        assert_eq!(keyfile.to_string(), "[A]\nk=v\n# x\ninvalid\n#b\n[B]\n");
+
+        let decor = keyfile.get_group_mut("B").unwrap().take_decor();
+        keyfile.set_decor(decor);
+        assert_eq!(keyfile.take_decor().lines(), ["# x", "invalid", "#b"]);
+        assert_eq!(keyfile.to_string(), "[A]\nk=v\n[B]\n");
+    }
+
+    #[test]
+    fn test_positions() {
+        let mut keyfile = KeyFile::parse("[A]\n[B]\n[C]\n").unwrap();
+        let names = |keyfile: &KeyFile| keyfile.groups.keys().map(ToString::to_string).collect::<Vec<_>>();
+
+        // existing groups are replaced and moved
+        let c = Group::new(GroupName::try_from("C").unwrap());
+        assert!(keyfile.insert_group_before("A", c).unwrap().is_some());
+        assert_eq!(names(&keyfile), ["C", "A", "B"]);
+        let c = Group::new(GroupName::try_from("C").unwrap());
+        assert!(keyfile.insert_group_after("B", c).unwrap().is_some());
+        assert_eq!(names(&keyfile), ["A", "B", "C"]);
+        let b = Group::new(GroupName::try_from("B").unwrap());
+        keyfile.insert_group_after("B", b).unwrap();
+        assert_eq!(names(&keyfile), ["A", "B", "C"]);
+
+        let d = Group::new(GroupName::try_from("D").unwrap());
+        assert!(matches!(
+            keyfile.insert_group_after("X", d),
+            Err(PositionError::GroupNotFound { ref name }) if name == "X"
+        ));
+        assert!(matches!(
+            keyfile.move_group("A", 3),
+            Err(PositionError::OutOfBounds { index: 3, len: 3 })
+        ));
+        keyfile.move_group("A", 2).unwrap();
+        assert_eq!(names(&keyfile), ["B", "C", "A"]);
+
+        // new translations are kept next to the base key
+        let group = keyfile.get_group_mut("A").unwrap();
+        group.set_string(Key::try_from("Name").unwrap(), "x").unwrap();
+        group.set_string(Key::try_from("Icon").unwrap(), "y").unwrap();
+        for locale in ["de", "fr"] {
+            let locale = Locale::try_from(locale).unwrap();
+            group
+                .set_locale_string(Key::try_from("Name").unwrap(), locale, "z")
+                .unwrap();
+        }
+        let locale = Locale::try_from("de").unwrap();
+        group
+            .set_locale_string(Key::try_from("Comment").unwrap(), locale.clone(), "z")
+            .unwrap();
+        assert_eq!(
+            group.to_string(),
+            "[A]\nName = x\nName[de] = z\nName[fr] = z\nIcon = y\nComment[de] = z\n"
+        );
+
+        let kv = KeyValuePair::new(Key::try_from("Comment").unwrap(), Value::try_from("c").unwrap());
+        group.insert_before("Comment", Some(&locale), kv).unwrap();
+        group.move_entry("Icon", None, 5).unwrap();
+        assert!(matches!(
+            group.move_entry("Name", Some(&Locale::try_from("es").unwrap()), 0),
+            Err(PositionError::KeyNotFound { ref key }) if key == "Name[es]"
+        ));
+        assert_eq!(
+            group.to_string(),
+            "[A]\nName = x\nName[de] = z\nName[fr] = z\nComment = c\nComment[de] = z\nIcon = y\n"
+        );
+    }
+
+    #[test]
+    fn test_rename_group() {
+        let input = "# top\n\n# a\n[A]\nk=v\n\n[B]\nk=w\n";
+        let mut keyfile = KeyFile::parse(input).unwrap();
+
+        keyfile.rename_group("A", GroupName::try_from("A").unwrap()).unwrap();
+        assert_eq!(keyfile.to_string(), input);
+        assert!(matches!(
+            keyfile.rename_group("C", GroupName::try_from("D").unwrap()),
+            Err(RenameGroupError::NotFound { ref name }) if name == "C"
+        ));
+
+        keyfile.duplicate_group("A", GroupName::try_from("C").unwrap()).unwrap();
+        assert!(matches!(
+            keyfile.duplicate_group("A", GroupName::try_from("B").unwrap()),
+            Err(RenameGroupError::Collision { ref name }) if name == "B"
+        ));
+        keyfile.rename_group("A", GroupName::try_from("Z").unwrap()).unwrap();
+
+        let c = keyfile.get_group_mut("C").unwrap();
+        assert_eq!(c.get_name(), "C");
+        c.set_string(Key::try_from("k").unwrap(), "c").unwrap();
+
+        assert_eq!(keyfile.get_group("Z").unwrap().get_string("k").unwrap().unwrap(), "v");
+        assert_eq!(
+            keyfile.to_string(),
+            "# top\n\n# a\n[Z]\nk=v\n\n# a\n[C]\nk=c\n\n[B]\nk=w\n"
+        );
+    }
+
+    #[test]
+    fn test_iterators() {
+        let input = "[A]\nName[de]=x\nIcon=y\nName[fr]=z\n[B]\n";
+        let mut keyfile = KeyFile::parse(input).unwrap();
+
+        for group in keyfile.groups_mut() {
+            for mut kv in group.entries_mut() {
+                kv.set_string("v").unwrap();
+            }
+            group.set_comment(Some("c")).unwrap();
+        }
+        assert_eq!(
+            keyfile.to_string(),
+            "#c\n[A]\nName[de]=v\nIcon=v\nName[fr]=v\n#c\n[B]\n"
+        );
+
+        let group = keyfile.get_group("A").unwrap();
+        assert_eq!(group.keys().collect::<Vec<_>>(), ["Name", "Icon"]);
+        assert_eq!(group.translations("Icon").count(), 0);
+        assert_eq!(group.translations("Name").count(), 2);
+        assert!(keyfile.get_group("B").unwrap().keys().next().is_none());
+    }
+
+    #[test]
+    fn test_set_lists() {
+        let mut keyfile = KeyFile::parse("[A]\n# strings\nStrings = x;\nLast=1\n").unwrap();
+        let group = keyfile.get_group_mut("A").unwrap();
+
+        let strings = ["a;b", "c,d", " e", "f\\g", ""];
+        group
+            .set_string_list(Key::try_from("Strings").unwrap(), &strings)
+            .unwrap();
+        group.set_boolean_list(Key::try_from("Booleans").unwrap(), &[true, false]);
+        group.set_integer_list(Key::try_from("Integers").unwrap(), &[-1, 0, i32::MAX]);
+        group.set_double_list(Key::try_from("Doubles").unwrap(), &[0.5, -2.0, 1.25]);
+        group.set_integer_list(Key::try_from("Empty").unwrap(), &[]);
+
+        // existing key-value pairs are updated in place
+        assert_eq!(
+            group.to_string(),
+            "[A]\n# strings\nStrings = a\\;b;c,d;\\se;f\\\\g;;\nLast=1\nBooleans = true;false;\n\
+             Integers = -1;0;2147483647;\nDoubles = 0.5;-2;1.25;\nEmpty = \n"
+        );
+
+        let reparsed = KeyFile::parse_with_options(&keyfile.to_string(), &ParseOptions::default())
+            .unwrap()
+            .into_owned();
+        let group = reparsed.get_group("A").unwrap();
+        assert_eq!(group.get_string_list("Strings").unwrap().unwrap(), strings);
+        assert_eq!(group.get_boolean_list("Booleans").unwrap().unwrap(), [true, false]);
+        assert_eq!(group.get_integer_list("Integers").unwrap().unwrap(), [-1, 0, i32::MAX]);
+        assert_eq!(group.get_double_list("Doubles").unwrap().unwrap(), [0.5, -2.0, 1.25]);
+        assert!(group.get_integer_list("Empty").unwrap().unwrap().is_empty());
+
+        // the list separator of the keyfile is used and escaped in elements
+        let mut keyfile = KeyFile::new_with_list_separator(ListSeparator::try_from(',').unwrap());
+        let group = keyfile.entry(GroupName::try_from("B").unwrap()).or_insert();
+        group
+            .set_string_list(Key::try_from("Strings").unwrap(), &["a;b", "c,d"])
+            .unwrap();
+        group.set_double_list(Key::try_from("Doubles").unwrap(), &[1.5]);
+        assert_eq!(group.to_string(), "[B]\nStrings = a;b,c\\,d,\nDoubles = 1.5,\n");
+        assert_eq!(group.get_string_list("Strings").unwrap().unwrap(), ["a;b", "c,d"]);
     }
 }
@@ -49,8 +49,14 @@
 //! [Desktop Entry Specification]: https://specifications.freedesktop.org/desktop-entry-spec/latest/
 //! [Glib.KeyFile]: https://docs.gtk.org/glib/struct.KeyFile.html
 
+mod entry;
+mod escape;
 mod keyfile;
 mod parse;
+mod span;
 pub mod types;
+mod write;
 
+pub use crate::entry::*;
 pub use crate::keyfile::*;
+pub use crate::span::*;
@@ -1,8 +1,27 @@
 use std::borrow::Cow;
+use std::collections::HashSet;
+use std::num::{IntErrorKind, ParseIntError};
+use std::ops::Range;
 
+use indexmap::IndexMap;
 use once_cell::sync::Lazy;
 use regex::Regex;
 
+use crate::keyfile::{
+    compound_key,
+    is_blank,
+    DuplicatePolicy,
+    Group,
+    InvalidLineReason,
+    KeyFile,
+    KeyFileError,
+    KeyValuePair,
+    LineEnding,
+    ParseOptions,
+    TrailingDecor,
+    ValueError,
+};
+use crate::span::{Position, Span};
 use crate::types::*;
 
 static HEADER: Lazy<Regex> = Lazy::new(|| {
@@ -29,11 +48,11 @@ static KEY_VALUE_PAIR: Lazy<Regex> = Lazy::new(|| {
         .expect(REGEX_ERROR)
 });
 
-pub fn parse_as_header(line: &str) -> Option<&str> {
+fn parse_as_header(line: &str) -> Option<&str> {
     Some(HEADER.captures(line)?.name("name")?.as_str())
 }
 
-pub fn parse_as_key_value_pair(line: &str) -> Option<(&str, Option<Locale>, &str, &str, &str)> {
+fn parse_as_key_value_pair(line: &str) -> Option<(&str, Option<Locale<'_>>, &str, &str, &str)> {
     let caps = KEY_VALUE_PAIR.captures(line)?;
 
     // key (compound key: name, optional locale) and value
@@ -59,6 +78,522 @@ pub fn parse_as_key_value_pair(line: &str) -> Option<(&str, Option<Locale>, &str
     Some((key, locale, value, wsl, wsr))
 }
 
+/// Parts of a group header line that are preserved in the [`Group`].
+struct Header<'a> {
+    name: &'a str,
+    indent: &'a str,
+    ws: &'a str,
+    cr: bool,
+}
+
+/// State of the line-based parser that is used to implement [`KeyFile::parse_with_options`] and
+/// [`KeyFile::parse_lenient`].
+pub(crate) struct Parser<'a, 'o> {
+    source: &'a str,
+    options: &'o ParseOptions,
+    // invalid lines are preserved and reported as warnings instead of errors
+    lenient: bool,
+    // comment lines at the top of the file, which are split off when the first group header is encountered
+    header: Decor<'a>,
+    started: bool,
+    groups: IndexMap<Cow<'a, str>, Group<'a>>,
+    current: Option<Group<'a>>,
+    // comments and empty lines (and invalid lines in lenient mode) that are not yet attached to an item
+    decor: Decor<'a>,
+    // number of lines at the start of `decor` that belong to the end of the current group
+    trailing: usize,
+    // the current group is shadowed by a previous group with the same name, its lines are only preserved
+    shadowed: bool,
+    // keys of the key-value pairs in the shadowed group, which are only used to detect duplicates
+    shadowed_keys: HashSet<(&'a str, Option<Locale<'a>>)>,
+    // key-value pairs with a locale specifier in the current group
+    translations: Vec<(&'a str, Locale<'a>, Span)>,
+    warnings: Vec<KeyFileError>,
+}
+
+impl<'a, 'o> Parser<'a, 'o> {
+    pub(crate) fn new(source: &'a str, options: &'o ParseOptions, lenient: bool) -> Self {
+        Parser {
+            source,
+            options,
+            lenient,
+            header: Decor::default(),
+            started: false,
+            groups: IndexMap::new(),
+            current: None,
+            decor: Decor::default(),
+            trailing: 0,
+            shadowed: false,
+            shadowed_keys: HashSet::new(),
+            translations: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    pub(crate) fn parse(mut self) -> Result<(KeyFile<'a>, Vec<KeyFileError>), KeyFileError> {
+        let value = self.source;
+
+        // CRLF line endings are only used if all lines end with CRLF, otherwise carriage returns are part of the lines
+        let crlf = value.contains('\n')
+            && value
+                .match_indices('\n')
+                .all(|(index, _)| value[..index].ends_with('\r'));
+
+        for (lineno, line) in value.split_inclusive('\n').enumerate() {
+            let line = match line.strip_suffix('\n') {
+                Some(line) if crlf => &line[..line.len() - 1],
+                Some(line) => line,
+                None => line,
+            };
+            self.parse_line(line, lineno)?;
+        }
+        self.finish_group()?;
+
+        // a file without any groups only consists of the comment at the top of the file
+        if !self.started {
+            self.header = std::mem::take(&mut self.decor);
+        }
+
+        let line_ending = if crlf { LineEnding::CrLf } else { LineEnding::Lf };
+        let final_newline = value.is_empty() || value.ends_with('\n');
+
+        let keyfile = KeyFile {
+            header: self.header,
+            groups: self.groups,
+            decor: self.decor,
+            list_separator: self.options.list_separator,
+            line_ending,
+            final_newline,
+        };
+
+        Ok((keyfile, self.warnings))
+    }
+
+    fn parse_line(&mut self, raw: &'a str, lineno: usize) -> Result<(), KeyFileError> {
+        // a carriage return that is not part of the line ending is preserved, but it is not part of the parsed item
+        let line = raw.strip_suffix('\r').unwrap_or(raw);
+        let cr = line.len() < raw.len();
+
+        // GLib ignores leading whitespace, which is preserved as indentation of the parsed item
+        let (indent, content) = if self.options.glib_compat {
+            let content = line.trim_start_matches(is_glib_space);
+            (&line[..line.len() - content.len()], content)
+        } else {
+            ("", line)
+        };
+
+        // - empty lines are not meaningful
+        // - lines that begin with a "#" character are comments
+        if content.is_empty() || content.starts_with('#') {
+            self.decor.push(Cow::Borrowed(raw));
+
+        // attempt to parse line as group header
+        } else if let Some((name, ws)) = self.parse_header(content) {
+            let header = Header { name, indent, ws, cr };
+            let is_current = !self.shadowed && self.current.as_ref().map_or(false, |group| group.name == name);
+            if is_current || self.groups.contains_key(name) {
+                let span = self.span(line, lineno, indent.len()..line.len() - ws.len());
+                let error = KeyFileError::duplicate_group(String::from(name), span);
+                return self.duplicate_group(error, header, raw, is_current);
+            }
+
+            self.finish_group()?;
+            self.start_group(header);
+
+        // lines of a shadowed group are preserved verbatim, but they are still checked
+        } else if self.shadowed {
+            if let Some((key, locale, ..)) = parse_as_key_value_pair(content) {
+                let compound_key = compound_key(key, locale.as_ref());
+                if !self.shadowed_keys.insert((key, locale)) {
+                    let key_span = self.span(line, lineno, indent.len()..indent.len() + key_len(content));
+                    self.warnings.push(KeyFileError::duplicate_key(compound_key, key_span));
+                }
+                self.decor.push_preserved(Cow::Borrowed(raw));
+            } else {
+                return self.invalid_line(raw, lineno, indent, content);
+            }
+
+        // attempt to parse line as key-value-pair
+        } else if let Some((key, locale, value, wsl, wsr)) = parse_as_key_value_pair(content) {
+            // key-value pairs are only valid after the first group header
+            let key_span = self.span(line, lineno, indent.len()..indent.len() + key_len(content));
+            let Some(collector) = &mut self.current else {
+                let span = self.span(line, lineno, 0..line.len());
+                let error = KeyFileError::ungrouped_key_value_pair(String::from(line), span);
+                return self.recover(error, raw);
+            };
+
+            // this clone is cheap since locale contains only Cow::Borrowed
+            let compound_key = (Cow::Borrowed(key), locale.clone());
+            if let Some(index) = collector.entries.get_index_of(&compound_key) {
+                let key_str = collector.entries[index].compound_key();
+                let error = KeyFileError::duplicate_key(key_str, key_span);
+
+                match self.options.duplicate_policy {
+                    DuplicatePolicy::Error => return Err(error),
+                    DuplicatePolicy::FirstWins => {
+                        self.warnings.push(error);
+                        self.preserve(raw);
+                        return Ok(());
+                    },
+                    DuplicatePolicy::LastWins | DuplicatePolicy::Merge => {
+                        self.warnings.push(error);
+
+                        // preserve the lines of the previous key-value pair at their original position
+                        if let Some((_, previous)) = collector.entries.shift_remove_index(index) {
+                            let raw_line = previous.raw_line();
+                            let mut lines = previous.decor;
+                            lines.push_preserved(Cow::Owned(raw_line));
+
+                            let decor = match collector.entries.get_index_mut(index) {
+                                Some((_, next)) => &mut next.decor,
+                                None => &mut self.decor,
+                            };
+                            lines.append(std::mem::take(decor));
+                            *decor = lines;
+                        }
+                    },
+                }
+            }
+
+            let mut kv = KeyValuePair::from_fields(
+                Key::new_unchecked(key.into()),
+                // this clone is cheap since locale contains only Cow::Borrowed
+                locale.clone(),
+                Value::new_unchecked(value.into()),
+                Whitespace::new_unchecked(wsl.into()),
+                Whitespace::new_unchecked(wsr.into()),
+                std::mem::take(&mut self.decor),
+            );
+            kv.indent = Cow::Borrowed(indent);
+            kv.cr = cr;
+            self.trailing = 0;
+
+            if let Some(locale) = &locale {
+                // this clone is cheap since locale contains only Cow::Borrowed
+                self.translations.push((key, locale.clone(), key_span));
+            }
+            collector.entries.insert(compound_key, kv);
+
+        // line is invalid if it is neither empty, nor a comment, nor a group header, nor a key-value-pair
+        } else {
+            return self.invalid_line(raw, lineno, indent, content);
+        }
+
+        Ok(())
+    }
+
+    /// Report a line that is neither empty, nor a comment, nor a group header, nor a key-value pair.
+    fn invalid_line(&mut self, raw: &'a str, lineno: usize, indent: &str, content: &str) -> Result<(), KeyFileError> {
+        let line = raw.strip_suffix('\r').unwrap_or(raw);
+        let (reason, range) = diagnose_invalid_line(content);
+        let span = self.span(line, lineno, indent.len() + range.start..indent.len() + range.end);
+        let error = KeyFileError::invalid_line(String::from(line), reason, span);
+        self.recover(error, raw)
+    }
+
+    /// Parse a line as group header, returning the group name and the whitespace after the header.
+    fn parse_header(&self, content: &'a str) -> Option<(&'a str, &'a str)> {
+        if let Some(name) = parse_as_header(content) {
+            return Some((name, ""));
+        }
+
+        // GLib silently accepts spaces and tabs after the "]" of a group header
+        if !self.options.glib_compat {
+            return None;
+        }
+        let trimmed = content.trim_end_matches(is_whitespace_char);
+        parse_as_header(trimmed).map(|name| (name, &content[trimmed.len()..]))
+    }
+
+    fn start_group(&mut self, header: Header<'a>) {
+        // lines up to the last empty line before the first group header are the comment at the top of the file
+        if !self.started {
+            let split = self.decor.lines().iter().rposition(|line| is_blank(line)).unwrap_or(0);
+            self.header = self.decor.split_to(split);
+            self.started = true;
+        }
+
+        let mut group = Group::from_entries(
+            GroupName::new_unchecked(header.name.into()),
+            IndexMap::new(),
+            std::mem::take(&mut self.decor),
+            self.options.list_separator,
+        );
+        group.indent = Cow::Borrowed(header.indent);
+        group.ws = Cow::Borrowed(header.ws);
+        group.cr = header.cr;
+        self.current = Some(group);
+        self.shadowed = false;
+    }
+
+    /// Resolve a group header for a group that already exists according to the configured [`DuplicatePolicy`].
+    fn duplicate_group(
+        &mut self,
+        error: KeyFileError,
+        header: Header<'a>,
+        line: &'a str,
+        is_current: bool,
+    ) -> Result<(), KeyFileError> {
+        match self.options.duplicate_policy {
+            DuplicatePolicy::Error => return Err(error),
+            DuplicatePolicy::FirstWins => {
+                // preserve all lines of the group verbatim until the next group header
+                self.finish_group()?;
+                self.decor.push_preserved(Cow::Borrowed(line));
+                self.shadowed = true;
+                self.shadowed_keys.clear();
+            },
+            DuplicatePolicy::LastWins => {
+                // preserve the lines of the previous group at its original position
+                self.finish_group()?;
+                if let Some((index, _, previous)) = self.groups.shift_remove_full(header.name) {
+                    let previous = previous.to_string();
+                    let lines = previous.split_terminator('\n').map(|line| Cow::Owned(line.to_owned()));
+                    let mut lines = Decor::new_preserved(lines.collect());
+
+                    let decor = match self.groups.get_index_mut(index) {
+                        Some((_, next)) => &mut next.decor,
+                        None => &mut self.decor,
+                    };
+                    lines.append(std::mem::take(decor));
+                    *decor = lines;
+                }
+                self.start_group(header);
+            },
+            DuplicatePolicy::Merge => {
+                // continue with the previous group, the header line itself is dropped
+                if !is_current {
+                    self.finish_group()?;
+                    if let Some(previous) = self.groups.get_mut(header.name) {
+                        let placeholder = Group::new(GroupName::new_unchecked(header.name.into()));
+                        self.current = Some(std::mem::replace(previous, placeholder));
+                        self.shadowed = false;
+                    }
+                }
+            },
+        }
+
+        self.warnings.push(error);
+        Ok(())
+    }
+
+    /// Preserve a line verbatim at the end of the current group (if there is no following key-value pair).
+    fn preserve(&mut self, line: &'a str) {
+        self.decor.push_preserved(Cow::Borrowed(line));
+        if self.current.is_some() {
+            self.trailing = self.decor.len();
+        }
+    }
+
+    /// Return the error in strict mode, or preserve the line and record the error as a warning in lenient mode.
+    fn recover(&mut self, error: KeyFileError, line: &'a str) -> Result<(), KeyFileError> {
+        if !self.lenient {
+            return Err(error);
+        }
+
+        self.preserve(line);
+        self.warnings.push(error);
+
+        Ok(())
+    }
+
+    fn finish_group(&mut self) -> Result<(), KeyFileError> {
+        let Some(mut group) = self.current.take() else {
+            return Ok(());
+        };
+
+        let trailing = match self.options.trailing_decor {
+            TrailingDecor::NextItem => self.trailing,
+            TrailingDecor::PreviousGroup => self.decor.len(),
+        };
+        group.trailing.append(self.decor.split_to(trailing));
+        self.trailing = 0;
+
+        // validate that there is a locale-less key-value-pair for every locale-ful key-value-pair
+        if self.options.check_base_keys {
+            for (key, locale, span) in self.translations.drain(..) {
+                if group.get(key, None).is_none() {
+                    let error = KeyFileError::missing_base_key(format!("{}[{}]", key, locale), span);
+                    if !self.lenient {
+                        return Err(error);
+                    }
+                    self.warnings.push(error);
+                }
+            }
+        }
+        self.translations.clear();
+
+        // this clone is cheap since group.name is always a Cow::Borrowed
+        // (a previous group with this name is only replaced when merging groups, which keeps its position)
+        self.groups.insert(group.name.clone(), group);
+
+        Ok(())
+    }
+
+    /// Span of the given range of bytes in a line of the source string.
+    fn span(&self, line: &'a str, lineno: usize, range: Range<usize>) -> Span {
+        let offset = line.as_ptr() as usize - self.source.as_ptr() as usize;
+
+        let position = |column| Position {
+            offset: offset + column,
+            line: lineno,
+            column,
+        };
+
+        Span {
+            start: position(range.start),
+            end: position(range.end),
+        }
+    }
+}
+
+/// Length of the compound key (including the locale specifier) at the start of a key-value pair.
+fn key_len(line: &str) -> usize {
+    line.find(|c: char| c == '=' || is_whitespace_char(c))
+        .unwrap_or(line.len())
+}
+
+/// Determine why a line could not be parsed, and which part of the line is responsible.
+///
+/// This is only called for lines that are already known to be invalid, so it does not need to be fast.
+fn diagnose_invalid_line(line: &str) -> (InvalidLineReason, Range<usize>) {
+    let char_at = |index: usize| index..index + line[index..].chars().next().map_or(0, char::len_utf8);
+    let end = line.len()..line.len();
+
+    let indent = line.len() - line.trim_start_matches(is_whitespace_char).len();
+    if indent > 0 {
+        return (InvalidLineReason::LeadingWhitespace, 0..indent);
+    }
+
+    // group header
+    if let Some(rest) = line.strip_prefix('[') {
+        let Some(close) = rest.find(']') else {
+            return (InvalidLineReason::UnterminatedHeader, end);
+        };
+
+        let name = &rest[..close];
+        if name.is_empty() || !name.chars().all(is_groupname_char) {
+            let span = invalid_char_span(name, is_groupname_char);
+            return (InvalidLineReason::InvalidGroupName, span.start + 1..span.end + 1);
+        }
+
+        return (InvalidLineReason::TrailingCharacters, close + 2..line.len());
+    }
+
+    // key with optional locale specifier
+    let mut position = invalid_char_span(line, is_key_char).start;
+    if position == 0 {
+        return (InvalidLineReason::InvalidKey, char_at(0));
+    }
+
+    if line[position..].starts_with('[') {
+        let Some(close) = line[position..].find(']') else {
+            return (InvalidLineReason::UnterminatedLocale, end);
+        };
+
+        let start = position + 1;
+        position += close + 1;
+
+        if let Some(span) = invalid_locale_span(&line[start..position - 1]) {
+            return (InvalidLineReason::InvalidLocale, start + span.start..start + span.end);
+        }
+    }
+
+    // separator
+    let separator = position + invalid_char_span(&line[position..], is_whitespace_char).start;
+    if !line[separator..].starts_with('=') {
+        if line[position..].contains('=') {
+            return (InvalidLineReason::InvalidKey, char_at(position));
+        }
+        return (InvalidLineReason::MissingSeparator, char_at(position));
+    }
+
+    // value
+    let value = separator + 1 + invalid_char_span(&line[separator + 1..], is_whitespace_char).start;
+    let span = invalid_char_span(&line[value..], is_value_char);
+    if span.start < line.len() - value {
+        return (InvalidLineReason::InvalidValue, value + span.start..value + span.end);
+    }
+
+    (InvalidLineReason::Unrecognized, 0..line.len())
+}
+
+/// Whitespace characters that are stripped from the start of lines by GLib (see `g_ascii_isspace`).
+fn is_glib_space(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\x0b' | '\x0c' | '\r')
+}
+
+// The following functions implement the same parsing rules as the corresponding functions in GLib, which means that
+// the handling of leading and trailing whitespace is not consistent between the different types.
+
+pub fn parse_as_boolean(value: &str) -> Result<bool, ValueError> {
+    // trailing whitespace is ignored, leading whitespace is not
+    match value.trim_end_matches(|c: char| c.is_ascii_whitespace()) {
+        "true" | "1" => Ok(true),
+        "false" | "0" => Ok(false),
+        _ => Err(ValueError::invalid_boolean(value)),
+    }
+}
+
+pub fn parse_as_integer(value: &str) -> Result<i32, ValueError> {
+    // leading whitespace is ignored, and like with strtol, parsing stops after the digits of the number
+    let number = value.trim_start_matches(is_glib_space);
+    let unsigned = number.strip_prefix(['+', '-']).unwrap_or(number);
+    let digits = unsigned.len() - unsigned.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+
+    // strtol does not consume any characters if there are no digits
+    let end = if digits == 0 {
+        0
+    } else {
+        value.len() - unsigned.len() + digits
+    };
+
+    // GLib only checks that the number is followed by whitespace, anything after that is ignored
+    if value.is_empty() || !value[end..].chars().next().map_or(true, is_glib_space) {
+        return Err(ValueError::invalid_number(value));
+    }
+    if digits == 0 {
+        return Ok(0);
+    }
+
+    value[value.len() - number.len()..end]
+        .parse()
+        .map_err(|error| integer_error(value, error))
+}
+
+pub fn parse_as_int64(value: &str) -> Result<i64, ValueError> {
+    // leading whitespace is ignored, trailing whitespace is not
+    value
+        .trim_start_matches(|c: char| c.is_ascii_whitespace())
+        .parse()
+        .map_err(|error| integer_error(value, error))
+}
+
+pub fn parse_as_uint64(value: &str) -> Result<u64, ValueError> {
+    // leading whitespace is ignored, trailing whitespace is not
+    value
+        .trim_start_matches(|c: char| c.is_ascii_whitespace())
+        .parse()
+        .map_err(|error| integer_error(value, error))
+}
+
+pub fn parse_as_double(value: &str) -> Result<f64, ValueError> {
+    // leading whitespace is ignored, trailing whitespace is not
+    // (parsing is independent of the current locale, i.e. the decimal separator is always ".")
+    value
+        .trim_start_matches(|c: char| c.is_ascii_whitespace())
+        .parse()
+        .map_err(|_| ValueError::invalid_number(value))
+}
+
+fn integer_error(value: &str, error: ParseIntError) -> ValueError {
+    match error.kind() {
+        IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => ValueError::out_of_range(value),
+        _ => ValueError::invalid_number(value),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::unwrap_used)]
@@ -120,4 +655,71 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn test_parse_as_boolean() {
+        assert!(parse_as_boolean("true").unwrap());
+        assert!(parse_as_boolean("1  ").unwrap());
+        assert!(!parse_as_boolean("false").unwrap());
+        assert!(!parse_as_boolean("0").unwrap());
+
+        assert!(parse_as_boolean(" true").is_err());
+        assert!(parse_as_boolean("True").is_err());
+        assert!(parse_as_boolean("yes").is_err());
+    }
+
+    #[test]
+    fn test_parse_as_numbers() {
+        assert_eq!(parse_as_integer(" -42 ").unwrap(), -42);
+        assert_eq!(parse_as_integer("+7").unwrap(), 7);
+        assert!(matches!(
+            parse_as_integer("2147483648"),
+            Err(ValueError::OutOfRange { .. })
+        ));
+        assert!(matches!(parse_as_integer(""), Err(ValueError::InvalidNumber { .. })));
+        assert!(matches!(parse_as_integer("4x"), Err(ValueError::InvalidNumber { .. })));
+        assert!(matches!(parse_as_integer("x"), Err(ValueError::InvalidNumber { .. })));
+
+        // like GLib, only the character after the number is checked
+        assert_eq!(parse_as_integer("4 2").unwrap(), 4);
+        assert_eq!(parse_as_integer("\t12\tpx").unwrap(), 12);
+        assert_eq!(parse_as_integer(" ").unwrap(), 0);
+
+        assert_eq!(parse_as_int64(" -9000000000").unwrap(), -9_000_000_000);
+        assert!(parse_as_int64("42 ").is_err());
+
+        assert_eq!(parse_as_uint64("18446744073709551615").unwrap(), u64::MAX);
+        assert!(matches!(parse_as_uint64("-1"), Err(ValueError::InvalidNumber { .. })));
+
+        assert_eq!(parse_as_double(" 1.5").unwrap(), 1.5);
+        assert_eq!(parse_as_double("-2e3").unwrap(), -2000.0);
+        assert!(parse_as_double("1,5").is_err());
+        assert!(parse_as_double("1.5 ").is_err());
+    }
+
+    #[test]
+    fn test_diagnose_invalid_line() {
+        use InvalidLineReason::*;
+
+        let cases = [
+            ("  Name=x", LeadingWhitespace, 0..2),
+            ("[Desktop Entry", UnterminatedHeader, 14..14),
+            ("[Desktop[Entry]", InvalidGroupName, 8..9),
+            ("[]", InvalidGroupName, 1..1),
+            ("[Desktop Entry] x", TrailingCharacters, 15..17),
+            ("Na.me=x", InvalidKey, 2..3),
+            ("Name x=y", InvalidKey, 4..5),
+            ("=x", InvalidKey, 0..1),
+            ("Name[de=x", UnterminatedLocale, 9..9),
+            ("Name[pt-BR]=x", InvalidLocale, 7..8),
+            ("Name[de_]=x", InvalidLocale, 8..8),
+            ("Icon", MissingSeparator, 4..4),
+            ("Name=\ta\tb", InvalidValue, 7..8),
+            ("Name=é\u{7f}", InvalidValue, 7..8),
+        ];
+
+        for (line, reason, range) in cases {
+            assert_eq!(diagnose_invalid_line(line), (reason, range), "{line:?}");
+        }
+    }
 }
@@ -0,0 +1,195 @@
+use std::fmt::Display;
+use std::ops::Range;
+
+/// ### Position in the source string of a [`KeyFile`](crate::KeyFile)
+///
+/// Positions are zero-based, and columns are counted in bytes from the start of the line.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Position {
+    /// byte offset from the start of the source string
+    pub offset: usize,
+    /// number of the line (zero-based, like [`KeyFileError::lineno`](crate::KeyFileError::lineno))
+    pub line: usize,
+    /// byte offset from the start of the line
+    pub column: usize,
+}
+
+/// ### Range of positions in the source string of a [`KeyFile`](crate::KeyFile)
+///
+/// The end position is exclusive, i.e. it points at the first byte after the range.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Span {
+    /// first position of the range
+    pub start: Position,
+    /// first position after the range
+    pub end: Position,
+}
+
+impl Span {
+    /// Method for getting the range of byte offsets that is covered by this span
+    pub fn range(&self) -> Range<usize> {
+        self.start.offset..self.end.offset
+    }
+
+    /// Span that starts at the start of this span and ends at the end of the other span.
+    pub(crate) fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start,
+            end: other.end,
+        }
+    }
+}
+
+/// ### Source string of a [`KeyFile`](crate::KeyFile) for looking up source spans
+///
+/// Parsing a string with [`KeyFile::parse`](crate::KeyFile::parse) does not copy any parts of the input string, so
+/// the location of the groups, key-value pairs, and comments in the input string can be recovered from the borrowed
+/// strings without storing any additional data. This type maps borrowed strings back to their [`Span`] in the input
+/// string. It is used by methods like [`KeyValuePair::value_span`](crate::KeyValuePair::value_span).
+///
+/// Parts of a [`KeyFile`](crate::KeyFile) that were modified after parsing (or copied with `into_owned`) do not point
+/// into the input string anymore, so no span is returned for them.
+///
+/// ```
+/// use keyfile::{KeyFile, Source};
+///
+/// let input = "[Desktop Entry]\n# comment\nName = Files\n";
+/// let keyfile = KeyFile::parse(input).unwrap();
+/// let source = Source::new(input);
+///
+/// let kv = keyfile.get_group("Desktop Entry").unwrap().get("Name", None).unwrap();
+/// let span = kv.value_span(&source).unwrap();
+/// assert_eq!(&input[span.range()], "Files");
+/// assert_eq!((span.start.line, span.start.column), (2, 7));
+///
+/// assert_eq!(kv.decor_spans(&source)[0].unwrap().start.line, 1);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Source<'s> {
+    text: &'s str,
+    // byte offsets of the first character of every line
+    line_starts: Vec<usize>,
+}
+
+impl<'s> Source<'s> {
+    /// Method for creating a new [`Source`] for the string that was parsed into a [`KeyFile`](crate::KeyFile)
+    pub fn new(text: &'s str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(text.match_indices('\n').map(|(index, _)| index + 1));
+        Source { text, line_starts }
+    }
+
+    /// Method for getting the source string
+    pub fn text(&self) -> &'s str {
+        self.text
+    }
+
+    /// ### Method for getting the span of a string that was borrowed from the source string
+    ///
+    /// If the string is not a part of the source string, then [`None`] is returned.
+    pub fn span_of(&self, value: &str) -> Option<Span> {
+        let start = (value.as_ptr() as usize).checked_sub(self.text.as_ptr() as usize)?;
+        let end = start.checked_add(value.len())?;
+
+        if end > self.text.len() {
+            return None;
+        }
+
+        Some(Span {
+            start: self.position(start),
+            end: self.position(end),
+        })
+    }
+
+    /// ### Method for getting the [`Position`] of a byte offset in the source string
+    ///
+    /// Offsets past the end of the source string are clamped to the end of the source string.
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.text.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+
+        Position {
+            offset,
+            line,
+            column: offset - self.line_starts[line],
+        }
+    }
+
+    /// Span of a string that was borrowed from the source string, extended by the given number of bytes on both sides.
+    pub(crate) fn span_around(&self, value: &str, before: usize, after: usize) -> Option<Span> {
+        let span = self.span_of(value)?;
+        let start = span.start.offset.checked_sub(before)?;
+        let end = span.end.offset.checked_add(after)?;
+
+        if end > self.text.len() {
+            return None;
+        }
+
+        Some(Span {
+            start: self.position(start),
+            end: self.position(end),
+        })
+    }
+}
+
+/// Render a message followed by a line of source text and a line of `^` characters that mark the given byte range.
+///
+/// The range is clamped to the line, and empty ranges are marked with a single `^` character.
+pub(crate) fn render_snippet(message: &dyn Display, line: &str, lineno: Option<usize>, range: Range<usize>) -> String {
+    let number = lineno.map(|lineno| lineno.to_string()).unwrap_or_default();
+    let gutter = " ".repeat(number.len());
+
+    let prefix = line.get(..range.start).unwrap_or(line);
+    let marked = line.get(range.start..range.end.min(line.len())).unwrap_or_default();
+
+    // keep tabs so the marker is aligned with the source line when it is displayed
+    let indent: String = prefix.chars().map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+    let marker = "^".repeat(marked.chars().count().max(1));
+
+    format!("{message}\n{gutter} |\n{number} | {line}\n{gutter} | {indent}{marker}\n")
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn test_span_of() {
+        let text = "[A]\r\nkey=value\n\nlast";
+        let source = Source::new(text);
+
+        let span = source.span_of(&text[9..14]).unwrap();
+        assert_eq!(span.range(), 9..14);
+        assert_eq!(
+            span.start,
+            Position {
+                offset: 9,
+                line: 1,
+                column: 4
+            }
+        );
+        assert_eq!(
+            span.end,
+            Position {
+                offset: 14,
+                line: 1,
+                column: 9
+            }
+        );
+
+        let span = source.span_of(&text[text.len()..]).unwrap();
+        assert_eq!(
+            span.start,
+            Position {
+                offset: 20,
+                line: 3,
+                column: 4
+            }
+        );
+
+        let owned = String::from("key");
+        assert!(source.span_of(&owned).is_none());
+    }
+}
@@ -11,6 +11,7 @@
 //! - [`Value`]: no control characters (including `\n` and `\r`)
 //! - [`Whitespace`]: space characters (` `) and / or TAB characters (`\t`)
 //! - [`Decor`]: list of strings that are either empty or start with the `#` character
+//! - [`ListSeparator`]: ASCII punctuation characters except `\`
 //!
 //!
 //! Additionally, this module contains the definition of [`Locale`], which is a composite of [`Language`], [`Country`]
@@ -21,11 +22,16 @@
 //! [Desktop Entry Specification]: https://specifications.freedesktop.org/desktop-entry-spec/latest/
 
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fmt::{self, Debug, Display};
+use std::hash::BuildHasher;
+use std::ops::Range;
 
 use once_cell::sync::Lazy;
 use regex::Regex;
 
+use crate::span::render_snippet;
+
 pub(crate) const REGEX_ERROR: &str = "Failed to compile hard-coded regular expression.";
 
 pub(crate) const GROUPNAME_REGEX: &str = r"[[:print:]&&[^\[\]]]+";
@@ -46,7 +52,7 @@ static MODIFIER: Lazy<Regex> = Lazy::new(|| Regex::new(&format!(r"^{MODIFIER_REG
 static VALUE: Lazy<Regex> = Lazy::new(|| Regex::new(&format!(r"^{VALUE_REGEX}$")).expect(REGEX_ERROR));
 static WHITESPACE: Lazy<Regex> = Lazy::new(|| Regex::new(&format!(r"^{WHITESPACE_REGEX}$")).expect(REGEX_ERROR));
 static LOCALE: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(&format!(r"(?<lang>{LANGUAGE_REGEX})(?:_(?<country>{COUNTRY_REGEX}))?(?:\.(?<encoding>{ENCODING_REGEX}))?(?:@(?<modifier>{MODIFIER_REGEX}))?")).expect(REGEX_ERROR)
+    Regex::new(&format!(r"^(?<lang>{LANGUAGE_REGEX})(?:_(?<country>{COUNTRY_REGEX}))?(?:\.(?<encoding>{ENCODING_REGEX}))?(?:@(?<modifier>{MODIFIER_REGEX}))?$")).expect(REGEX_ERROR)
 });
 
 /// ## Error that is returned when attempting to initialize a type with an invalid input for that type
@@ -54,38 +60,165 @@ static LOCALE: Lazy<Regex> = Lazy::new(|| {
 /// The newtype wrappers in this module ensure that only valid strings can be used for manually building keyfiles.
 /// Attempting to construct a type from a string that is invalid for that specific type will yield one of the variants
 /// of this error.
+///
+/// Except for [`InvalidString::ListSeparator`], the error contains the range of bytes in the input string that caused
+/// the error (usually the first invalid character, or an empty range at the end of the string if the string is
+/// incomplete):
+///
+/// ```
+/// use keyfile::types::Key;
+///
+/// let error = Key::try_from("Na.me").unwrap_err();
+/// assert_eq!(error.span(), Some(2..3));
+/// assert_eq!(error.render("Na.me"), format!("{error}\n |\n | Na.me\n |   ^\n"));
+/// ```
 #[derive(Debug, thiserror::Error)]
 pub enum InvalidString {
     /// An invalid string was passed to [`GroupName::try_from`].
-    #[error("Invalid group name: may only contain printable ASCII, except for the '[' and ']' characters")]
-    GroupName,
+    #[error("Invalid group name (column {}): may only contain printable ASCII, except for the '[' and ']' characters", .span.start)]
+    #[allow(missing_docs)]
+    GroupName { span: Range<usize> },
     /// An invalid string was passed to [`Key::try_from`].
-    #[error("Invalid key name: may only contain alphanumeric ASCII characters and the '-' character")]
-    Key,
+    #[error("Invalid key name (column {}): may only contain alphanumeric ASCII characters and the '-' character", .span.start)]
+    #[allow(missing_docs)]
+    Key { span: Range<usize> },
     /// An invalid string was passed to [`Language::try_from`].
-    #[error("Invalid lanugage: may only contain alphabetic ASCII characters")]
-    Language,
+    #[error("Invalid lanugage (column {}): may only contain alphabetic ASCII characters", .span.start)]
+    #[allow(missing_docs)]
+    Language { span: Range<usize> },
     /// An invalid string was passed to [`Country::try_from`].
-    #[error("Invalid country: may only contaun alphabetic ASCII characters")]
-    Country,
+    #[error("Invalid country (column {}): may only contaun alphabetic ASCII characters", .span.start)]
+    #[allow(missing_docs)]
+    Country { span: Range<usize> },
     /// An invalid string was passed to [`Encoding::try_from`].
-    #[error("Invalid encoding: may only contain alphanumeric ASCII characters and the '-' character")]
-    Encoding,
+    #[error("Invalid encoding (column {}): may only contain alphanumeric ASCII characters and the '-' character", .span.start)]
+    #[allow(missing_docs)]
+    Encoding { span: Range<usize> },
     /// An invalid string was passed to [`Modifier::try_from`].
-    #[error("Invalid modifier: may only contain alphabetic ASCII characters")]
-    Modifier,
+    #[error("Invalid modifier (column {}): may only contain alphabetic ASCII characters", .span.start)]
+    #[allow(missing_docs)]
+    Modifier { span: Range<usize> },
     /// An invalid string was passed to [`Value::try_from`].
-    #[error("Invalid value: may not contain control characters")]
-    Value,
+    #[error("Invalid value (column {}): may not contain control characters", .span.start)]
+    #[allow(missing_docs)]
+    Value { span: Range<usize> },
     /// An invalid string was passed to [`Whitespace::try_from`].
-    #[error("Invalid whitespace: may only contain space (' ') or tab ('\t')")]
-    Whitespace,
-    /// An invalid list of strings was passed to [`Decor::try_from`].
-    #[error("Invalid decor: may only contain empty strings or strings that start with the '#' character")]
-    Decor,
+    #[error("Invalid whitespace (column {}): may only contain space (' ') or tab ('\t')", .span.start)]
+    #[allow(missing_docs)]
+    Whitespace { span: Range<usize> },
+    /// An invalid list of strings was passed to [`Decor::try_from`]. The span refers to the string at the given index.
+    #[error("Invalid decor (line {}): may only contain empty strings or strings that start with the '#' character", .index)]
+    #[allow(missing_docs)]
+    Decor { index: usize, span: Range<usize> },
+    /// An invalid comment text was passed to [`Group::set_comment`](crate::Group::set_comment) or
+    /// [`KeyValuePair::set_comment`](crate::KeyValuePair::set_comment).
+    #[error("Invalid comment (column {}): may not contain control characters other than tab and line breaks", .span.start)]
+    #[allow(missing_docs)]
+    Comment { span: Range<usize> },
     /// An invalid string was passed to [`Locale::try_from`].
-    #[error("Invalid locale: unrecognized format")]
-    Locale,
+    #[error("Invalid locale (column {}): unrecognized format", .span.start)]
+    #[allow(missing_docs)]
+    Locale { span: Range<usize> },
+    /// An invalid character was passed to [`ListSeparator::try_from`].
+    #[error("Invalid list separator: may only be an ASCII punctuation character, except for the '\\' character")]
+    ListSeparator,
+}
+
+impl InvalidString {
+    /// Method for getting the range of bytes in the input string that caused the error
+    pub fn span(&self) -> Option<Range<usize>> {
+        match self {
+            InvalidString::GroupName { span }
+            | InvalidString::Key { span }
+            | InvalidString::Language { span }
+            | InvalidString::Country { span }
+            | InvalidString::Encoding { span }
+            | InvalidString::Modifier { span }
+            | InvalidString::Value { span }
+            | InvalidString::Whitespace { span }
+            | InvalidString::Decor { span, .. }
+            | InvalidString::Comment { span }
+            | InvalidString::Locale { span } => Some(span.clone()),
+            InvalidString::ListSeparator => None,
+        }
+    }
+
+    /// Method for getting the (zero-based) byte offset of the first byte in the input string that caused the error
+    pub fn column(&self) -> Option<usize> {
+        self.span().map(|span| span.start)
+    }
+
+    /// ### Method for rendering the error message together with the input string that caused the error
+    ///
+    /// The input string is printed below the error message, and the part of the input string that caused the error is
+    /// marked with `^` characters. For [`InvalidString::Decor`] errors, the string at the reported index needs to be
+    /// passed to this method.
+    pub fn render(&self, value: &str) -> String {
+        render_snippet(self, value, None, self.span().unwrap_or_default())
+    }
+}
+
+/// Range of the first character in the string that does not match the given predicate (or an empty range at the end
+/// of the string if there is no such character).
+pub(crate) fn invalid_char_span(value: &str, valid: impl Fn(char) -> bool) -> Range<usize> {
+    match value.char_indices().find(|(_, c)| !valid(*c)) {
+        Some((index, c)) => index..index + c.len_utf8(),
+        None => value.len()..value.len(),
+    }
+}
+
+pub(crate) fn is_groupname_char(c: char) -> bool {
+    matches!(c, ' '..='~') && c != '[' && c != ']'
+}
+
+pub(crate) fn is_key_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-'
+}
+
+pub(crate) fn is_alpha_char(c: char) -> bool {
+    c.is_ascii_alphabetic()
+}
+
+pub(crate) fn is_value_char(c: char) -> bool {
+    !c.is_ascii_control()
+}
+
+pub(crate) fn is_whitespace_char(c: char) -> bool {
+    c == ' ' || c == '\t'
+}
+
+/// Range of the first character that does not fit the `lang_COUNTRY.ENCODING@MODIFIER` format of locale strings, or
+/// [`None`] if the string is a valid locale string.
+pub(crate) fn invalid_locale_span(value: &str) -> Option<Range<usize>> {
+    if LOCALE.is_match(value) {
+        return None;
+    }
+
+    // language (required), then country, encoding, and modifier (optional) with their respective prefix characters
+    let mut position = 0;
+    for prefix in [None, Some('_'), Some('.'), Some('@')] {
+        // only the encoding may contain digits and "-" characters
+        let valid: fn(char) -> bool = if prefix == Some('.') {
+            is_key_char
+        } else {
+            is_alpha_char
+        };
+
+        let start = match prefix {
+            None => position,
+            Some(prefix) if value[position..].starts_with(prefix) => position + 1,
+            Some(_) => continue,
+        };
+
+        let span = invalid_char_span(&value[start..], valid);
+        if span.start == 0 {
+            return Some(start + span.start..start + span.end);
+        }
+        position = start + span.start;
+    }
+
+    let span = invalid_char_span(&value[position..], |_| false);
+    Some(position + span.start..position + span.end)
 }
 
 /// ## Newtype struct wrapping strings that are valid group names
@@ -133,7 +266,9 @@ impl<'a> TryFrom<Cow<'a, str>> for GroupName<'a> {
 
     fn try_from(value: Cow<'a, str>) -> Result<Self, Self::Error> {
         if !GROUPNAME.is_match(&value) {
-            return Err(InvalidString::GroupName);
+            return Err(InvalidString::GroupName {
+                span: invalid_char_span(&value, is_groupname_char),
+            });
         }
 
         Ok(GroupName { inner: value })
@@ -204,7 +339,9 @@ impl<'a> TryFrom<Cow<'a, str>> for Key<'a> {
 
     fn try_from(value: Cow<'a, str>) -> Result<Self, Self::Error> {
         if !KEY.is_match(&value) {
-            return Err(InvalidString::Key);
+            return Err(InvalidString::Key {
+                span: invalid_char_span(&value, is_key_char),
+            });
         }
 
         Ok(Key { inner: value })
@@ -274,7 +411,9 @@ impl<'a> TryFrom<Cow<'a, str>> for Language<'a> {
 
     fn try_from(value: Cow<'a, str>) -> Result<Self, Self::Error> {
         if !LANGUAGE.is_match(&value) {
-            return Err(InvalidString::Language);
+            return Err(InvalidString::Language {
+                span: invalid_char_span(&value, is_alpha_char),
+            });
         }
 
         Ok(Language { inner: value })
@@ -345,7 +484,9 @@ impl<'a> TryFrom<Cow<'a, str>> for Country<'a> {
 
     fn try_from(value: Cow<'a, str>) -> Result<Self, Self::Error> {
         if !COUNTRY.is_match(&value) {
-            return Err(InvalidString::Country);
+            return Err(InvalidString::Country {
+                span: invalid_char_span(&value, is_alpha_char),
+            });
         }
 
         Ok(Country { inner: value })
@@ -415,7 +556,9 @@ impl<'a> TryFrom<Cow<'a, str>> for Encoding<'a> {
 
     fn try_from(value: Cow<'a, str>) -> Result<Self, Self::Error> {
         if !ENCODING.is_match(&value) {
-            return Err(InvalidString::Encoding);
+            return Err(InvalidString::Encoding {
+                span: invalid_char_span(&value, is_key_char),
+            });
         }
 
         Ok(Encoding { inner: value })
@@ -485,7 +628,9 @@ impl<'a> TryFrom<Cow<'a, str>> for Modifier<'a> {
 
     fn try_from(value: Cow<'a, str>) -> Result<Self, Self::Error> {
         if !MODIFIER.is_match(&value) {
-            return Err(InvalidString::Modifier);
+            return Err(InvalidString::Modifier {
+                span: invalid_char_span(&value, is_alpha_char),
+            });
         }
 
         Ok(Modifier { inner: value })
@@ -556,7 +701,9 @@ impl<'a> TryFrom<Cow<'a, str>> for Value<'a> {
 
     fn try_from(value: Cow<'a, str>) -> Result<Self, Self::Error> {
         if !VALUE.is_match(&value) {
-            return Err(InvalidString::Value);
+            return Err(InvalidString::Value {
+                span: invalid_char_span(&value, is_value_char),
+            });
         }
 
         Ok(Value { inner: value })
@@ -658,7 +805,9 @@ impl<'a> TryFrom<Cow<'a, str>> for Whitespace<'a> {
 
     fn try_from(value: Cow<'a, str>) -> Result<Self, Self::Error> {
         if !WHITESPACE.is_match(&value) {
-            return Err(InvalidString::Whitespace);
+            return Err(InvalidString::Whitespace {
+                span: invalid_char_span(&value, is_whitespace_char),
+            });
         }
 
         Ok(Whitespace { inner: value })
@@ -704,15 +853,107 @@ impl<'a> TryFrom<String> for Whitespace<'a> {
 ///
 /// let inner: Vec<Cow<str>> = Decor::try_from(vec![""]).unwrap().into();
 /// ```
-#[derive(Clone, Debug)]
+///
+/// Decor that is taken from a parsed [`KeyFile`](crate::KeyFile) (for example, with [`Group::take_decor`]) can also
+/// contain lines that are not accepted by [`Decor::try_from`], like indented comment lines (see
+/// [`ParseOptions::glib_compat`](crate::ParseOptions::glib_compat)), invalid lines that are kept by
+/// [`KeyFile::parse_lenient`](crate::KeyFile::parse_lenient), or the lines of duplicate groups and key-value pairs.
+/// These lines are kept when the decor is moved to a different item, but invalid and duplicate lines are never treated
+/// as comment lines.
+///
+/// [`Group::take_decor`]: crate::Group::take_decor
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct Decor<'a> {
     inner: Vec<Cow<'a, str>>,
+    // number of lines at the start that were preserved verbatim by the parser instead of being parsed as comment lines
+    // or empty lines (all lines up to the last preserved line, since comments only consist of the following lines)
+    preserved: usize,
 }
 
 impl<'a> Decor<'a> {
     #[inline(always)]
     pub(crate) fn new_unchecked<'v: 'a>(value: Vec<Cow<'a, str>>) -> Self {
-        Decor { inner: value }
+        Decor {
+            inner: value,
+            preserved: 0,
+        }
+    }
+
+    /// Decor that only consists of lines that are preserved verbatim.
+    pub(crate) fn new_preserved(value: Vec<Cow<'a, str>>) -> Self {
+        Decor {
+            preserved: value.len(),
+            inner: value,
+        }
+    }
+
+    /// All lines, including preserved lines.
+    pub(crate) fn lines(&self) -> &[Cow<'a, str>] {
+        &self.inner
+    }
+
+    /// Mutable access to all lines, which does not allow adding or removing lines.
+    pub(crate) fn lines_mut(&mut self) -> &mut [Cow<'a, str>] {
+        &mut self.inner
+    }
+
+    /// Lines after the last preserved line, which can contain comment lines.
+    pub(crate) fn comment_lines(&self) -> &[Cow<'a, str>] {
+        &self.inner[self.preserved..]
+    }
+
+    /// Replace a range of the lines after the last preserved line (the range is relative to these lines).
+    pub(crate) fn splice_comment_lines<I>(&mut self, range: Range<usize>, lines: I)
+    where
+        I: IntoIterator<Item = Cow<'a, str>>,
+    {
+        let range = self.preserved + range.start..self.preserved + range.end;
+        self.inner.splice(range, lines);
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Append a comment line or an empty line.
+    pub(crate) fn push(&mut self, line: Cow<'a, str>) {
+        self.inner.push(line);
+    }
+
+    /// Append a line that is preserved verbatim.
+    pub(crate) fn push_preserved(&mut self, line: Cow<'a, str>) {
+        self.inner.push(line);
+        self.preserved = self.inner.len();
+    }
+
+    /// Append all lines of another decor, keeping its preserved lines.
+    pub(crate) fn append(&mut self, other: Decor<'a>) {
+        if other.preserved > 0 {
+            self.preserved = self.inner.len() + other.preserved;
+        }
+        self.inner.extend(other.inner);
+    }
+
+    /// Remove the first lines and return them as a separate decor.
+    pub(crate) fn split_to(&mut self, at: usize) -> Decor<'a> {
+        let preserved = self.preserved.min(at);
+        self.preserved -= preserved;
+
+        Decor {
+            inner: self.inner.drain(..at).collect(),
+            preserved,
+        }
+    }
+
+    pub(crate) fn into_owned(self) -> Decor<'static> {
+        Decor {
+            inner: self
+                .inner
+                .into_iter()
+                .map(|line| Cow::Owned(line.into_owned()))
+                .collect(),
+            preserved: self.preserved,
+        }
     }
 }
 
@@ -727,13 +968,14 @@ impl<'a> TryFrom<Vec<Cow<'a, str>>> for Decor<'a> {
     type Error = InvalidString;
 
     fn try_from(value: Vec<Cow<'a, str>>) -> Result<Self, Self::Error> {
-        for line in &value {
+        for (index, line) in value.iter().enumerate() {
             if !line.is_empty() && !line.starts_with('#') {
-                return Err(InvalidString::Decor);
+                let span = invalid_char_span(line, |_| false);
+                return Err(InvalidString::Decor { index, span });
             }
         }
 
-        Ok(Decor { inner: value })
+        Ok(Decor::new_unchecked(value))
     }
 }
 
@@ -753,6 +995,57 @@ impl<'a> TryFrom<Vec<String>> for Decor<'a> {
     }
 }
 
+/// ## Newtype struct wrapping characters that are valid list separators
+///
+/// New instances of `ListSeparator` can only be created from characters that can be used to separate the elements of
+/// list values. Letters and digits are not allowed since they would be ambiguous with escape sequences like `\s`:
+///
+/// ```
+/// use keyfile::types::ListSeparator;
+///
+/// let separator = ListSeparator::try_from(',').unwrap();
+/// let error = ListSeparator::try_from('s').unwrap_err();
+/// ```
+///
+/// The default list separator is the `;` character. The inner character can always be obtained by using the
+/// [`From::from`] method:
+///
+/// ```
+/// use keyfile::types::ListSeparator;
+///
+/// let inner: char = ListSeparator::default().into();
+/// assert_eq!(inner, ';');
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ListSeparator {
+    inner: char,
+}
+
+impl Default for ListSeparator {
+    fn default() -> Self {
+        ListSeparator { inner: ';' }
+    }
+}
+
+impl From<ListSeparator> for char {
+    #[inline(always)]
+    fn from(value: ListSeparator) -> Self {
+        value.inner
+    }
+}
+
+impl TryFrom<char> for ListSeparator {
+    type Error = InvalidString;
+
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        if !value.is_ascii_punctuation() || value == '\\' {
+            return Err(InvalidString::ListSeparator);
+        }
+
+        Ok(ListSeparator { inner: value })
+    }
+}
+
 /// ## Locale identifier (language, country / territory, encoding, and modifier)
 ///
 /// This struct represents a locale identifier as used on UNIX / POSIX systems.
@@ -771,28 +1064,33 @@ impl<'a> TryFrom<&'a str> for Locale<'a> {
     type Error = InvalidString;
 
     fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        if let Some(span) = invalid_locale_span(value) {
+            return Err(InvalidString::Locale { span });
+        }
+
         let Some(caps) = LOCALE.captures(value) else {
-            return Err(InvalidString::Locale);
+            return Err(InvalidString::Locale { span: 0..value.len() });
         };
 
         let Some(lang) = caps.name("lang").map(|m| m.as_str()) else {
-            return Err(InvalidString::Locale);
+            return Err(InvalidString::Locale { span: 0..value.len() });
         };
 
         let country = caps.name("country").map(|m| m.as_str());
-        let encoding = caps.name("encoding").map(|m| m.as_str());
         let modifier = caps.name("modifier").map(|m| m.as_str());
 
-        if encoding.is_some() {
+        if let Some(encoding) = caps.name("encoding") {
             // This is an error: Constructing an encoding modifier is not supported since only UTF-8 encoded strings
             // can be set as values, so no valid value could be set for a KeyValuePair with this Locale set.
-            return Err(InvalidString::Encoding);
+            return Err(InvalidString::Encoding {
+                span: encoding.start() - 1..encoding.end(),
+            });
         }
 
         Ok(Locale::new_with_encoding(
             Language::new_unchecked(Cow::Borrowed(lang)),
             country.map(|c| Country::new_unchecked(Cow::Borrowed(c))),
-            encoding.map(|e| Encoding::new_unchecked(Cow::Borrowed(e))),
+            None,
             modifier.map(|m| Modifier::new_unchecked(Cow::Borrowed(m))),
         ))
     }
@@ -844,7 +1142,7 @@ impl<'a> Locale<'a> {
     /// ### Method for setting the language identifier
     ///
     /// The replaced string is returned.
-    pub fn set_lang<'l: 'a>(&mut self, lang: Language<'l>) -> Cow<str> {
+    pub fn set_lang<'l: 'a>(&mut self, lang: Language<'l>) -> Cow<'a, str> {
         std::mem::replace(&mut self.lang, lang.into())
     }
 
@@ -856,7 +1154,7 @@ impl<'a> Locale<'a> {
     /// ### Method for getting the country / territory identifier
     ///
     /// If this method replaces an existing identifier, it is returned.
-    pub fn set_country<'c: 'a>(&mut self, country: Option<Country<'c>>) -> Option<Cow<str>> {
+    pub fn set_country<'c: 'a>(&mut self, country: Option<Country<'c>>) -> Option<Cow<'a, str>> {
         std::mem::replace(&mut self.country, country.map(Into::into))
     }
 
@@ -873,9 +1171,129 @@ impl<'a> Locale<'a> {
     /// ### Method for setting the locale modifier
     ///
     /// If this method replaces an existing modifier, it is returned.
-    pub fn set_modifier<'m: 'a>(&mut self, modifier: Option<Modifier<'m>>) -> Option<Cow<str>> {
+    pub fn set_modifier<'m: 'a>(&mut self, modifier: Option<Modifier<'m>>) -> Option<Cow<'a, str>> {
         std::mem::replace(&mut self.modifier, modifier.map(Into::into))
     }
+
+    /// ### Method for getting the list of locales that match this locale, in order of preference
+    ///
+    /// This implements the matching rules for localized values from the Desktop Entry Specification: For a locale of
+    /// the form `lang_COUNTRY@MODIFIER`, the returned list contains `lang_COUNTRY@MODIFIER`, `lang_COUNTRY`,
+    /// `lang@MODIFIER`, and `lang` (in this order). Variants that do not apply (for example, because the locale has no
+    /// country) are skipped. The encoding is not used for matching, so it is not included in any of the returned
+    /// locales.
+    ///
+    /// ```
+    /// use keyfile::types::Locale;
+    ///
+    /// let locale = Locale::try_from("sr_RS@latin").unwrap();
+    /// let fallbacks: Vec<String> = locale.fallbacks().iter().map(ToString::to_string).collect();
+    ///
+    /// assert_eq!(fallbacks, ["sr_RS@latin", "sr_RS", "sr@latin", "sr"]);
+    /// ```
+    pub fn fallbacks(&self) -> Vec<Locale<'_>> {
+        let mut fallbacks = Vec::with_capacity(4);
+        push_fallbacks(
+            &mut fallbacks,
+            &self.lang,
+            self.country.as_deref(),
+            self.modifier.as_deref(),
+        );
+        fallbacks
+    }
+
+    /// ### Method for getting the list of preferred locales from environment variables
+    ///
+    /// This method determines the user's preferred locales from the same variables as `g_get_language_names` in GLib:
+    ///
+    /// - The value of the first variable of `LANGUAGE`, `LC_ALL`, `LC_MESSAGES`, and `LANG` that is set to a non-empty
+    ///   value is used. Only `LANGUAGE` can contain a colon-separated list of locales, in order of preference.
+    /// - The encoding of every locale is stripped, and the implied fallbacks of every locale are added directly after it.
+    ///   The fallbacks follow the order of the Desktop Entry Specification (see [`Locale::fallbacks`]), i.e.
+    ///   `lang_COUNTRY` is preferred over `lang@MODIFIER`. GLib uses the opposite order for these two variants.
+    /// - The `C` locale is always added as the last element of the list.
+    ///
+    /// Entries that are not valid locale identifiers are skipped. The variables are passed as an explicit map, so the
+    /// process environment can be used by collecting [`std::env::vars`] into a [`HashMap`]:
+    ///
+    /// ```
+    /// use keyfile::types::Locale;
+    /// use std::collections::HashMap;
+    ///
+    /// let vars = HashMap::from([
+    ///     (String::from("LANGUAGE"), String::from("sr@latin:de_AT")),
+    ///     (String::from("LANG"), String::from("de_AT.UTF-8")),
+    /// ]);
+    /// let locales: Vec<String> = Locale::from_env_vars(&vars).iter().map(ToString::to_string).collect();
+    ///
+    /// assert_eq!(locales, ["sr@latin", "sr", "de_AT", "de", "C"]);
+    ///
+    /// // the process environment can be used by collecting its variables:
+    /// let vars: HashMap<String, String> = std::env::vars().collect();
+    /// let locales = Locale::from_env_vars(&vars);
+    /// ```
+    pub fn from_env_vars<S: BuildHasher>(vars: &'a HashMap<String, String, S>) -> Vec<Locale<'a>> {
+        let value = ["LANGUAGE", "LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| vars.get(*name))
+            .map(String::as_str)
+            .find(|value| !value.is_empty())
+            .unwrap_or("C");
+
+        let mut locales = Vec::new();
+
+        for entry in value.split(':') {
+            let Some(caps) = LOCALE.captures(entry) else {
+                continue;
+            };
+
+            // the C locale is only added at the end, even if it has an encoding or modifier (like `C.UTF-8`)
+            let Some(lang) = caps.name("lang").map(|m| m.as_str()) else {
+                continue;
+            };
+            if lang == "C" || lang == "POSIX" {
+                continue;
+            }
+            let country = caps.name("country").map(|m| m.as_str());
+            let modifier = caps.name("modifier").map(|m| m.as_str());
+
+            push_fallbacks(&mut locales, lang, country, modifier);
+        }
+
+        push_fallbacks(&mut locales, "C", None, None);
+        locales
+    }
+
+    /// Method for checking whether two locales are equal, ignoring their encodings
+    pub(crate) fn matches(&self, other: &Locale) -> bool {
+        self.lang == other.lang && self.country == other.country && self.modifier == other.modifier
+    }
+}
+
+/// Append the variants of a locale (in order of preference) to a list, skipping variants that are already present.
+fn push_fallbacks<'l>(
+    locales: &mut Vec<Locale<'l>>,
+    lang: &'l str,
+    country: Option<&'l str>,
+    modifier: Option<&'l str>,
+) {
+    let variants = [(country, modifier), (country, None), (None, modifier), (None, None)];
+
+    for (country, modifier) in variants {
+        if locales
+            .iter()
+            .any(|l| l.lang == lang && l.country.as_deref() == country && l.modifier.as_deref() == modifier)
+        {
+            continue;
+        }
+
+        locales.push(Locale {
+            lang: Cow::Borrowed(lang),
+            country: country.map(Cow::Borrowed),
+            encoding: None,
+            modifier: modifier.map(Cow::Borrowed),
+        });
+    }
 }
 
 impl<'a> Display for Locale<'a> {
@@ -893,3 +1311,64 @@ impl<'a> Display for Locale<'a> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    fn locales(vars: &[(&str, &str)]) -> Vec<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| (String::from(*name), String::from(*value)))
+            .collect();
+        Locale::from_env_vars(&vars).iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_from_env_vars_priority() {
+        let all = [
+            ("LANGUAGE", "fr"),
+            ("LC_ALL", "de"),
+            ("LC_MESSAGES", "es"),
+            ("LANG", "it"),
+        ];
+        assert_eq!(locales(&all), ["fr", "C"]);
+        assert_eq!(locales(&all[1..]), ["de", "C"]);
+        assert_eq!(locales(&all[2..]), ["es", "C"]);
+        assert_eq!(locales(&all[3..]), ["it", "C"]);
+        assert_eq!(locales(&[]), ["C"]);
+
+        // empty variables are skipped
+        assert_eq!(
+            locales(&[("LANGUAGE", ""), ("LANG", "it_IT.UTF-8")]),
+            ["it_IT", "it", "C"]
+        );
+    }
+
+    #[test]
+    fn test_from_env_vars_variants() {
+        assert_eq!(
+            locales(&[("LANG", "de_DE.UTF-8@euro")]),
+            ["de_DE@euro", "de_DE", "de@euro", "de", "C"]
+        );
+        assert_eq!(
+            locales(&[("LANGUAGE", "de_AT:de_DE:en")]),
+            ["de_AT", "de", "de_DE", "en", "C"]
+        );
+
+        // the C and POSIX locales (with or without encoding) only result in the C locale
+        assert_eq!(locales(&[("LANG", "C.UTF-8")]), ["C"]);
+        assert_eq!(locales(&[("LC_ALL", "POSIX")]), ["C"]);
+        assert_eq!(locales(&[("LANGUAGE", "C:de")]), ["de", "C"]);
+        assert_eq!(locales(&[("LANGUAGE", "C.UTF-8:de")]), ["de", "C"]);
+        assert_eq!(locales(&[("LANGUAGE", "POSIX.UTF-8:fr:POSIX@euro")]), ["fr", "C"]);
+
+        // invalid entries are skipped
+        assert_eq!(
+            locales(&[("LANGUAGE", "pt-BR::en_US:fr FR:sr@latin")]),
+            ["en_US", "en", "sr@latin", "sr", "C"]
+        );
+    }
+}
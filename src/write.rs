@@ -0,0 +1,36 @@
+use std::fmt::{self, Arguments};
+
+/// Helper for writing lines with a configurable line ending.
+///
+/// The line ending is written lazily (i.e. before the *next* line), which makes it possible to omit the line ending
+/// after the last line.
+pub(crate) struct LineWriter<'f, 'w> {
+    f: &'f mut fmt::Formatter<'w>,
+    line_ending: &'static str,
+    pending: bool,
+}
+
+impl<'f, 'w> LineWriter<'f, 'w> {
+    pub(crate) fn new(f: &'f mut fmt::Formatter<'w>, line_ending: &'static str) -> Self {
+        LineWriter {
+            f,
+            line_ending,
+            pending: false,
+        }
+    }
+
+    pub(crate) fn line(&mut self, args: Arguments<'_>) -> fmt::Result {
+        if self.pending {
+            self.f.write_str(self.line_ending)?;
+        }
+        self.pending = true;
+        self.f.write_fmt(args)
+    }
+
+    pub(crate) fn finish(self, final_newline: bool) -> fmt::Result {
+        if self.pending && final_newline {
+            self.f.write_str(self.line_ending)?;
+        }
+        Ok(())
+    }
+}
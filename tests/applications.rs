@@ -2,7 +2,7 @@
 
 use glob::glob;
 
-use keyfile::KeyFile;
+use keyfile::{KeyFile, ParseOptions};
 
 #[test]
 fn parse_all() -> anyhow::Result<()> {
@@ -31,3 +31,25 @@ fn parse_all() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn parse_all_lenient() -> anyhow::Result<()> {
+    let files = glob("/usr/share/applications/*.desktop")?;
+
+    for entry in files {
+        let path = entry?;
+        println!("Checking {}", path.display());
+
+        let contents = std::fs::read_to_string(path)?;
+        let (parsed, warnings) = KeyFile::parse_lenient(contents.as_str(), &ParseOptions::default())?;
+        let written = parsed.to_string();
+
+        for warning in warnings {
+            println!("  {}", warning);
+        }
+
+        assert_eq!(written, contents);
+    }
+
+    Ok(())
+}